@@ -0,0 +1,56 @@
+fn main() {
+    #[cfg(feature = "cadical")]
+    cadical_shim();
+}
+
+/// Compile the functions for presets and proof tracing, which the C API of
+/// CaDiCaL lacks
+///
+/// The handle of the C API points to the `Wrapper` defined in `ccadical.cpp` of
+/// CaDiCaL 1.3.0, the version the `cadical` crate is pinned to. It derives from
+/// the `Learner` and `Terminator` interfaces, their vtable pointers precede the
+/// solver. Only non-inline members of the solver are called, so no header of
+/// CaDiCaL is needed.
+#[cfg(feature = "cadical")]
+fn cadical_shim() {
+    const SHIM: &str = r#"
+namespace CaDiCaL {
+class Solver {
+public:
+  bool configure (const char * name);
+  bool trace_proof (const char * path);
+  void flush_proof_trace ();
+};
+}
+
+struct Wrapper {
+  void * learner;
+  void * terminator;
+  CaDiCaL::Solver * solver;
+};
+
+extern "C" {
+
+int aba2sat_cadical_configure (void * wrapper, const char * name) {
+  return ((Wrapper *) wrapper)->solver->configure (name);
+}
+
+int aba2sat_cadical_trace_proof (void * wrapper, const char * path) {
+  return ((Wrapper *) wrapper)->solver->trace_proof (path);
+}
+
+void aba2sat_cadical_flush_proof_trace (void * wrapper) {
+  ((Wrapper *) wrapper)->solver->flush_proof_trace ();
+}
+
+}
+"#;
+    let out = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    let path = out.join("cadical_shim.cpp");
+    std::fs::write(&path, SHIM).unwrap();
+    cc::Build::new()
+        .cpp(true)
+        .file(path)
+        .compile("aba2sat_cadical");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,188 @@
+//! Check extensions without the SAT solver
+//!
+//! The semantics are checked on the [`Aba`] as given, by forward chaining on its
+//! rules. Neither the preparation of the framework nor the encoding is involved,
+//! which makes these checks an independent certificate for the solver answers.
+//!
+//! A set of assumptions `S` defends an assumption `a` if every argument for the
+//! contrary of `a` uses an assumption attacked by `S`. In a flat framework this is
+//! the case if the contrary cannot be derived from the assumptions not attacked by `S`.
+use std::collections::HashSet;
+
+use super::{Aba, Num};
+
+/// Why a set of assumptions is not an extension
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Violation {
+    #[error("{_0} is not an assumption of the framework")]
+    UnknownAssumption(Num),
+    #[error("the set attacks its own assumption {_0}")]
+    Conflict(Num),
+    #[error("the set does not defend its assumption {_0}")]
+    Undefended(Num),
+    #[error("the set defends the assumption {_0} without containing it")]
+    DefendedOutside(Num),
+    #[error("the set neither contains nor attacks the assumption {_0}")]
+    NotAttacked(Num),
+}
+
+/// All atoms derivable from `assumptions`, the rules are applied until nothing changes
+pub fn closure(aba: &Aba, assumptions: &HashSet<Num>) -> HashSet<Num> {
+    let mut closure = assumptions.clone();
+    loop {
+        let size = closure.len();
+        for (head, body) in &aba.rules {
+            if body.iter().all(|atom| closure.contains(atom)) {
+                closure.insert(*head);
+            }
+        }
+        if closure.len() == size {
+            return closure;
+        }
+    }
+}
+
+/// Assumptions whose contrary is derivable from `assumptions`
+pub fn attacked(aba: &Aba, assumptions: &HashSet<Num>) -> HashSet<Num> {
+    let closure = closure(aba, assumptions);
+    aba.inverses
+        .iter()
+        .filter(|(_, inverse)| closure.contains(inverse))
+        .map(|(assumption, _)| *assumption)
+        .collect()
+}
+
+/// Assumptions defended by `assumptions`
+pub fn defended(aba: &Aba, assumptions: &HashSet<Num>) -> HashSet<Num> {
+    let attacked = attacked(aba, assumptions);
+    let unattacked = aba
+        .assumptions()
+        .filter(|assumption| !attacked.contains(assumption))
+        .cloned()
+        .collect();
+    let derivable = closure(aba, &unattacked);
+    aba.inverses
+        .iter()
+        .filter(|(_, inverse)| !derivable.contains(inverse))
+        .map(|(assumption, _)| *assumption)
+        .collect()
+}
+
+pub fn conflict_free(aba: &Aba, assumptions: &HashSet<Num>) -> Result<(), Violation> {
+    if let Some(unknown) = sorted(assumptions)
+        .into_iter()
+        .find(|assumption| !aba.contains_assumption(assumption))
+    {
+        return Err(Violation::UnknownAssumption(unknown));
+    }
+    let attacked = attacked(aba, assumptions);
+    match sorted(assumptions)
+        .into_iter()
+        .find(|assumption| attacked.contains(assumption))
+    {
+        Some(assumption) => Err(Violation::Conflict(assumption)),
+        None => Ok(()),
+    }
+}
+
+pub fn admissible(aba: &Aba, assumptions: &HashSet<Num>) -> Result<(), Violation> {
+    conflict_free(aba, assumptions)?;
+    let defended = defended(aba, assumptions);
+    match sorted(assumptions)
+        .into_iter()
+        .find(|assumption| !defended.contains(assumption))
+    {
+        Some(assumption) => Err(Violation::Undefended(assumption)),
+        None => Ok(()),
+    }
+}
+
+pub fn complete(aba: &Aba, assumptions: &HashSet<Num>) -> Result<(), Violation> {
+    admissible(aba, assumptions)?;
+    let defended = defended(aba, assumptions);
+    match sorted(&defended)
+        .into_iter()
+        .find(|assumption| !assumptions.contains(assumption))
+    {
+        Some(assumption) => Err(Violation::DefendedOutside(assumption)),
+        None => Ok(()),
+    }
+}
+
+pub fn stable(aba: &Aba, assumptions: &HashSet<Num>) -> Result<(), Violation> {
+    conflict_free(aba, assumptions)?;
+    let attacked = attacked(aba, assumptions);
+    let all = aba.assumptions().cloned().collect();
+    match sorted(&all)
+        .into_iter()
+        .find(|assumption| !assumptions.contains(assumption) && !attacked.contains(assumption))
+    {
+        Some(assumption) => Err(Violation::NotAttacked(assumption)),
+        None => Ok(()),
+    }
+}
+
+/// Report the smallest offending assumption, independent of the hashing
+fn sorted(set: &HashSet<Num>) -> Vec<Num> {
+    let mut list: Vec<_> = set.iter().cloned().collect();
+    list.sort_unstable();
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aba::debug::DebugAba;
+
+    use super::*;
+
+    #[test]
+    fn semantics_of_a_chain_and_a_cycle() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'q')
+            .with_assumption('c', 'r')
+            .with_assumption('d', 's')
+            .with_assumption('e', 't')
+            // c attacks b, b attacks a
+            .with_rule('q', ['c'])
+            .with_rule('p', ['b'])
+            // d and e attack each other, e attacks itself together with c
+            .with_rule('t', ['d'])
+            .with_rule('s', ['e'])
+            .with_rule('t', ['c', 'e']);
+        let set = |atoms: &[char]| -> HashSet<Num> {
+            atoms
+                .iter()
+                .map(|atom| aba.forward_atom(*atom).unwrap())
+                .collect()
+        };
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let aba = aba.aba();
+        assert_eq!(closure(aba, &set(&['c'])), set(&['c', 'q']));
+        assert_eq!(
+            conflict_free(aba, &set(&['c', 'e'])),
+            Err(Violation::Conflict(atom('e')))
+        );
+        assert_eq!(
+            admissible(aba, &set(&['a'])),
+            Err(Violation::Undefended(atom('a')))
+        );
+        assert_eq!(admissible(aba, &set(&['a', 'c'])), Ok(()));
+        assert_eq!(
+            complete(aba, &set(&['c'])),
+            Err(Violation::DefendedOutside(atom('a')))
+        );
+        assert_eq!(complete(aba, &set(&['a', 'c'])), Ok(()));
+        assert_eq!(complete(aba, &set(&['a', 'c', 'd'])), Ok(()));
+        assert_eq!(
+            stable(aba, &set(&['a', 'c'])),
+            Err(Violation::NotAttacked(atom('d')))
+        );
+        assert_eq!(stable(aba, &set(&['a', 'c', 'd'])), Ok(()));
+        // Atoms that are not assumptions cannot be part of an extension
+        assert_eq!(
+            stable(aba, &set(&['a', 'c', 'q'])),
+            Err(Violation::UnknownAssumption(atom('q')))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Native computation of the grounded extension
+//!
+//! The grounded extension is the least fixpoint of the characteristic function. An
+//! assumption is defended by a set `S`, if its contrary cannot be derived without
+//! using an assumption attacked by `S`. Starting with the empty set, the defended
+//! assumptions are collected until nothing changes.
+use std::collections::HashSet;
+
+use super::{index::AbaIndex, Aba, Num};
+
+/// The grounded extension and the assumptions it attacks
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Grounded {
+    /// Assumptions of the grounded extension, these are part of every complete extension
+    pub accepted: HashSet<Num>,
+    /// Assumptions attacked by the grounded extension, these are never part of an admissible extension
+    pub defeated: HashSet<Num>,
+}
+
+impl Grounded {
+    #[cfg_attr(
+        feature = "timing",
+        fun_time::fun_time(message = "Computing grounded extension", reporting = "log")
+    )]
+    pub fn of(aba: &Aba, index: &AbaIndex) -> Self {
+        let mut grounded = Grounded::default();
+        loop {
+            // Assumptions attacked by the current set, facts attack even for the empty set
+            let derivable = derivable_from(aba, index, grounded.accepted.iter());
+            grounded.defeated = aba
+                .inverses
+                .iter()
+                .filter(|(_, inverse)| derivable.contains(inverse))
+                .map(|(assumption, _)| *assumption)
+                .collect();
+            // Everything derivable without the defeated assumptions
+            let derivable = derivable_from(
+                aba,
+                index,
+                aba.assumptions()
+                    .filter(|assumption| !grounded.defeated.contains(assumption)),
+            );
+            let accepted: HashSet<_> = aba
+                .inverses
+                .iter()
+                .filter(|(_, inverse)| !derivable.contains(inverse))
+                .map(|(assumption, _)| *assumption)
+                .collect();
+            if accepted.len() == grounded.accepted.len() {
+                break;
+            }
+            grounded.accepted = accepted;
+        }
+        log::info!(
+            "Grounded extension: {} accepted, {} defeated assumptions",
+            grounded.accepted.len(),
+            grounded.defeated.len()
+        );
+        grounded
+    }
+}
+
+/// All atoms derivable from `assumptions` by forward chaining
+pub fn derivable_from<'n, I: IntoIterator<Item = &'n Num>>(
+    aba: &Aba,
+    index: &AbaIndex,
+    assumptions: I,
+) -> HashSet<Num> {
+    let mut derivable = HashSet::new();
+    // Number of body atoms per rule that are not yet derived
+    let mut missing: Vec<_> = aba.rules.iter().map(|(_, body)| body.len()).collect();
+    let mut queue: Vec<Num> = assumptions
+        .into_iter()
+        .cloned()
+        .chain(
+            aba.rules
+                .iter()
+                .filter(|(_, body)| body.is_empty())
+                .map(|(head, _)| *head),
+        )
+        .collect();
+    while let Some(atom) = queue.pop() {
+        if !derivable.insert(atom) {
+            continue;
+        }
+        for rule_id in index.rules_with_body(&atom) {
+            missing[*rule_id] -= 1;
+            if missing[*rule_id] == 0 {
+                queue.push(aba.rules[*rule_id].0);
+            }
+        }
+    }
+    derivable
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aba::debug::DebugAba;
+
+    use super::*;
+
+    #[test]
+    fn grounded_defends_against_attacked_attackers() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'q')
+            .with_assumption('c', 'r')
+            .with_assumption('d', 's')
+            .with_assumption('e', 't')
+            // c attacks b, b attacks a
+            .with_rule('q', ['c'])
+            .with_rule('p', ['b'])
+            // d and e attack each other
+            .with_rule('t', ['d'])
+            .with_rule('s', ['e']);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let grounded = Grounded::of(aba.aba(), &AbaIndex::new(aba.aba()));
+        assert_eq!(
+            grounded.accepted,
+            [atom('a'), atom('c')].into_iter().collect()
+        );
+        assert_eq!(grounded.defeated, [atom('b')].into_iter().collect());
+    }
+
+    #[test]
+    fn facts_defeat_assumptions_of_the_empty_set() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'a')
+            .with_rule('p', []);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let grounded = Grounded::of(aba.aba(), &AbaIndex::new(aba.aba()));
+        assert_eq!(grounded.accepted, [atom('b')].into_iter().collect());
+        assert_eq!(grounded.defeated, [atom('a')].into_iter().collect());
+    }
+}
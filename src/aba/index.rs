@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use bit_set::BitSet;
+
+use super::{Aba, Num};
+
+/// Indexed, compact view on the atoms and rules of an [`Aba`]
+///
+/// Every atom of the framework is assigned a dense index, which is used to store the
+/// adjacency between atoms and rules in a compressed form. This allows answering
+/// questions like "which rules derive `p`" without scanning all rules.
+///
+/// The index is a snapshot, any change to the [`Aba`] requires a new index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AbaIndex {
+    /// All atoms of the framework, sorted and without duplicates
+    atoms: Vec<Num>,
+    /// Translation from atom to its position in `atoms`
+    positions: HashMap<Num, usize>,
+    /// Indices of all assumptions
+    assumptions: BitSet,
+    /// Rules grouped by head
+    heads: Adjacency,
+    /// Rules grouped by the atoms of their body
+    bodies: Adjacency,
+}
+
+/// Compressed adjacency list from atom indices to rule ids
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Adjacency {
+    /// The rules of atom `i` are `rules[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<usize>,
+    rules: Vec<usize>,
+}
+
+impl AbaIndex {
+    pub fn new(aba: &Aba) -> Self {
+        let mut atoms: Vec<Num> = aba
+            .inverses
+            .iter()
+            .flat_map(|(assumption, inverse)| [*assumption, *inverse])
+            .chain(
+                aba.rules
+                    .iter()
+                    .flat_map(|(head, body)| std::iter::once(head).chain(body).cloned()),
+            )
+            .collect();
+        atoms.sort_unstable();
+        atoms.dedup();
+        let positions: HashMap<_, _> = atoms
+            .iter()
+            .enumerate()
+            .map(|(idx, atom)| (*atom, idx))
+            .collect();
+        let assumptions = aba.assumptions().map(|a| positions[a]).collect();
+        let heads = Adjacency::new(
+            atoms.len(),
+            aba.rules
+                .iter()
+                .enumerate()
+                .map(|(rule_id, (head, _))| (positions[head], rule_id)),
+        );
+        let bodies = Adjacency::new(
+            atoms.len(),
+            aba.rules
+                .iter()
+                .enumerate()
+                .flat_map(|(rule_id, (_, body))| body.iter().map(move |atom| (atom, rule_id)))
+                .map(|(atom, rule_id)| (positions[atom], rule_id)),
+        );
+        AbaIndex {
+            atoms,
+            positions,
+            assumptions,
+            heads,
+            bodies,
+        }
+    }
+
+    /// All atoms in ascending order
+    pub fn atoms(&self) -> &[Num] {
+        &self.atoms
+    }
+
+    /// Number of atoms in the framework
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    /// The dense index of `atom`, if it is part of the framework
+    pub fn position(&self, atom: &Num) -> Option<usize> {
+        self.positions.get(atom).copied()
+    }
+
+    /// The atom with the dense index `position`
+    pub fn atom(&self, position: usize) -> Num {
+        self.atoms[position]
+    }
+
+    pub fn contains_atom(&self, atom: &Num) -> bool {
+        self.positions.contains_key(atom)
+    }
+
+    pub fn is_assumption(&self, atom: &Num) -> bool {
+        self.position(atom)
+            .is_some_and(|position| self.assumptions.contains(position))
+    }
+
+    /// Ids of all rules with `atom` as their head
+    pub fn rules_with_head(&self, atom: &Num) -> &[usize] {
+        match self.position(atom) {
+            Some(position) => self.heads.get(position),
+            None => &[],
+        }
+    }
+
+    /// Ids of all rules containing `atom` in their body
+    pub fn rules_with_body(&self, atom: &Num) -> &[usize] {
+        match self.position(atom) {
+            Some(position) => self.bodies.get(position),
+            None => &[],
+        }
+    }
+}
+
+impl Adjacency {
+    fn new<I: IntoIterator<Item = (usize, usize)>>(atoms: usize, edges: I) -> Self {
+        let edges: Vec<_> = edges.into_iter().collect();
+        // Count the rules per atom and turn the counts into offsets
+        let mut offsets = vec![0; atoms + 1];
+        for (atom, _) in &edges {
+            offsets[*atom + 1] += 1;
+        }
+        for idx in 1..offsets.len() {
+            offsets[idx] += offsets[idx - 1];
+        }
+        // Fill the slots, `next` tracks the next free slot per atom
+        let mut next = offsets.clone();
+        let mut rules = vec![0; edges.len()];
+        for (atom, rule_id) in edges {
+            rules[next[atom]] = rule_id;
+            next[atom] += 1;
+        }
+        Adjacency { offsets, rules }
+    }
+
+    fn get(&self, position: usize) -> &[usize] {
+        &self.rules[self.offsets[position]..self.offsets[position + 1]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aba::debug::DebugAba;
+
+    use super::*;
+
+    #[test]
+    fn index_simple_aba() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_rule('p', ['a', 'q'])
+            .with_rule('q', [])
+            .with_rule('p', ['q']);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let index = AbaIndex::new(aba.aba());
+        assert_eq!(index.len(), 3);
+        assert!(index.is_assumption(&atom('a')));
+        assert!(!index.is_assumption(&atom('p')));
+        assert!(!index.contains_atom(&42));
+        assert_eq!(index.rules_with_head(&atom('p')), &[0, 2]);
+        assert_eq!(index.rules_with_head(&atom('q')), &[1]);
+        assert_eq!(index.rules_with_head(&atom('a')), &[] as &[usize]);
+        assert_eq!(index.rules_with_body(&atom('q')), &[0, 2]);
+        assert_eq!(index.rules_with_body(&atom('a')), &[0]);
+        assert_eq!(index.atom(index.position(&atom('q')).unwrap()), atom('q'));
+    }
+}
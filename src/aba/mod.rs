@@ -5,7 +5,7 @@
 //! ## Example
 //! ```
 //! # use aba2sat::aba::debug::DebugAba;
-//! # use aba2sat::aba::problems::solve;
+//! # use aba2sat::aba::problems::{solve, Config};
 //! # use aba2sat::aba::problems::admissibility::VerifyAdmissibleExtension;
 //! let aba =
 //!     // Start with an empty framework
@@ -22,21 +22,35 @@
 //! let atom = aba.forward_atom('b').unwrap();
 //! let assumptions = vec![atom].into_iter().collect();
 //! let result =
-//!     solve(VerifyAdmissibleExtension { assumptions }, aba.aba().clone(), None).unwrap();
+//!     solve(VerifyAdmissibleExtension { assumptions }, aba.aba().clone(), &Config::default()).unwrap();
 //!
 //! // The result should be true
 //! assert!(result)
 //! ```
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    time::Instant,
+};
 
-use crate::literal::RawLiteral;
+use crate::{
+    cancel::CancellationToken,
+    literal::lits::{LoopHelper, RuleBodyActive, TheoryAtom},
+};
 
+pub mod check;
 pub mod debug;
+pub mod grounded;
+mod index;
 mod prepared;
 pub mod problems;
+pub mod simplify;
+pub mod slice;
+pub mod statistics;
+pub mod symmetry;
 mod theory;
 mod traverse;
 
+pub use index::AbaIndex;
 pub use prepared::PreparedAba;
 pub use traverse::{Loop, Loops};
 
@@ -83,7 +97,13 @@ impl Aba {
     }
 
     pub fn contains_atom(&self, elem: &Num) -> bool {
-        self.universe().any(|e| *e == *elem)
+        self.inverses
+            .iter()
+            .any(|(assumption, inverse)| assumption == elem || inverse == elem)
+            || self
+                .rules
+                .iter()
+                .any(|(head, body)| head == elem || body.contains(elem))
     }
 
     #[cfg(debug_assertions)]
@@ -101,6 +121,14 @@ impl Aba {
     }
 
     /// Prepare this aba for translation to SAT
+    pub fn prepare(self, max_loops: Option<usize>) -> PreparedAba {
+        self.prepare_until(max_loops, None, &CancellationToken::new(), true)
+    }
+
+    /// Prepare this aba, but stop searching loops once `deadline` passed or
+    /// `cancel` is cancelled
+    ///
+    /// The framework is only simplified if `simplify` is set, see [`simplify`].
     #[cfg_attr(
         feature = "timing",
         fun_time::fun_time(
@@ -108,29 +136,40 @@ impl Aba {
             reporting = "log"
         )
     )]
-    pub fn prepare(self, max_loops: Option<usize>) -> PreparedAba {
-        PreparedAba::new(self, max_loops)
-    }
-
-    fn rule_heads(&self) -> impl Iterator<Item = &Num> + '_ {
-        self.rules.iter().map(|(head, _)| head)
+    pub fn prepare_until(
+        self,
+        max_loops: Option<usize>,
+        deadline: Option<Instant>,
+        cancel: &CancellationToken,
+        simplify: bool,
+    ) -> PreparedAba {
+        PreparedAba::new(self, max_loops, deadline, cancel, simplify)
     }
 }
 
-pub trait Context {
-    type Base: From<Num> + Into<RawLiteral> + 'static;
-    type Rule: From<usize> + Into<RawLiteral> + 'static;
-    type Loop: From<usize> + Into<RawLiteral> + 'static;
-}
+/// A copy of the theory of the framework in the SAT encoding
+///
+/// Every context has its own variables for the atoms, rule helpers and loop helpers,
+/// see [`PreparedAba::derive_clauses`]. The first two copies hold the candidate set
+/// and its attackers, problems may use any number of additional copies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Context(pub usize);
+
+impl Context {
+    /// Theory derived from the candidate set, see [`Candidate`](crate::literal::lits::Candidate)
+    pub const CANDIDATE: Context = Context(0);
+    /// Theory derived by the attackers, see [`Attacker`](crate::literal::lits::Attacker)
+    pub const ATTACKER: Context = Context(1);
+
+    pub fn atom(self, atom: Num) -> TheoryAtom {
+        TheoryAtom(self.0, atom)
+    }
 
-impl Context for crate::literal::lits::Candidate {
-    type Base = Self;
-    type Rule = crate::literal::lits::CandidateRuleBodyActive;
-    type Loop = crate::literal::lits::CandidateLoopHelper;
-}
+    pub fn rule(self, rule_id: usize) -> RuleBodyActive {
+        RuleBodyActive(self.0, rule_id)
+    }
 
-impl Context for crate::literal::lits::Attacker {
-    type Base = Self;
-    type Rule = crate::literal::lits::AttackerRuleBodyActive;
-    type Loop = crate::literal::lits::AttackerLoopHelper;
+    pub fn r#loop(self, loop_id: usize) -> LoopHelper {
+        LoopHelper(self.0, loop_id)
+    }
 }
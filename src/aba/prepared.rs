@@ -1,8 +1,20 @@
-use std::collections::{BTreeSet, HashSet};
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Instant,
+};
 
-use crate::{aba::Num, clauses::Clause, literal::IntoLiteral};
+use bit_set::BitSet;
 
-use super::{theory::theory_helper, traverse::Loops, Aba, Context};
+use crate::{aba::Num, cancel::CancellationToken, clauses::Clause, literal::IntoLiteral};
+
+use super::{
+    index::AbaIndex,
+    simplify,
+    statistics::{Encoding, Statistics},
+    theory::theory_helper,
+    traverse::{has_cycles, Loops},
+    Aba, Context,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Loop {
@@ -13,25 +25,129 @@ struct Loop {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PreparedAba {
     aba: Aba,
+    index: AbaIndex,
+    /// Atoms removed by the simplification and their equivalent replacement
+    representatives: HashMap<Num, Num>,
     loops: Vec<Loop>,
+    /// Rules that are the only rule for their head in an acyclic framework
+    ///
+    /// These are encoded directly on their head, without a rule helper.
+    inlined_rules: BitSet,
+    statistics: Statistics,
 }
 
 impl PreparedAba {
     /// Create a new [`PreparedAba`] from a raw [`Aba`]
-    pub fn new(mut aba: Aba, max_loops: Option<usize>) -> Self {
+    ///
+    /// Loops are searched until `deadline` or until `cancel` is cancelled, see
+    /// [`Loops::until`]. The framework is simplified first if `simplify` is set.
+    pub fn new(
+        mut aba: Aba,
+        max_loops: Option<usize>,
+        deadline: Option<Instant>,
+        cancel: &CancellationToken,
+        simplify: bool,
+    ) -> Self {
         trim_trivial_cycles(&mut aba);
-        trim_unreachable_rules(&mut aba);
-        let loops = match max_loops {
-            Some(0) => vec![],
-            _ => calculate_loops_and_their_support(&aba, max_loops).collect(),
+        let (simplification, representatives) = match simplify {
+            true => simplify::simplify(&mut aba),
+            false => Default::default(),
         };
-        PreparedAba { aba, loops }
+        trim_unreachable_rules(&mut aba);
+        // The rules are final now, index them
+        let index = AbaIndex::new(&aba);
+        if has_cycles(&aba) {
+            let loops: Vec<_> = match max_loops {
+                Some(0) => vec![],
+                _ => calculate_loops_and_their_support(&aba, &index, max_loops, deadline, cancel)
+                    .collect(),
+            };
+            let statistics = Statistics {
+                encoding: Encoding::Cyclic,
+                loops: loops.len(),
+                inlined_rules: 0,
+                simplification,
+                shortcut: None,
+            };
+            PreparedAba {
+                aba,
+                index,
+                representatives,
+                loops,
+                inlined_rules: BitSet::new(),
+                statistics,
+            }
+        } else {
+            // Without cycles, no loop can be found and every head
+            // with a single body can be encoded without a rule helper
+            let inlined_rules = single_body_rules(&index);
+            let statistics = Statistics {
+                encoding: Encoding::Acyclic,
+                loops: 0,
+                inlined_rules: inlined_rules.len(),
+                simplification,
+                shortcut: None,
+            };
+            PreparedAba {
+                aba,
+                index,
+                representatives,
+                loops: vec![],
+                inlined_rules,
+                statistics,
+            }
+        }
+    }
+
+    /// The index of the prepared framework
+    pub fn index(&self) -> &AbaIndex {
+        &self.index
+    }
+
+    /// Whether `atom` occurs anywhere in the prepared framework
+    ///
+    /// Atoms merged by the simplification are still part of the framework,
+    /// see [`PreparedAba::representative`].
+    pub fn contains_atom(&self, atom: &Num) -> bool {
+        self.index.contains_atom(atom) || self.representatives.contains_key(atom)
+    }
+
+    /// The atom used in the encoding in place of `atom`
+    ///
+    /// This is `atom` itself, unless it was merged with an equivalent atom.
+    /// Returns `None` if the atom is not part of the encoding, these can never be derived.
+    pub fn representative(&self, atom: Num) -> Option<Num> {
+        let atom = self.representatives.get(&atom).copied().unwrap_or(atom);
+        self.index.contains_atom(&atom).then_some(atom)
+    }
+
+    /// Statistics about the preparation of this framework
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    pub(crate) fn statistics_mut(&mut self) -> &mut Statistics {
+        &mut self.statistics
     }
+
+    /// Number of loops that will be broken by the encoding
+    pub fn loop_count(&self) -> usize {
+        self.loops.len()
+    }
+
+    /// Whether the rule with index `rule_id` is encoded without a rule helper
+    pub fn is_inlined(&self, rule_id: usize) -> bool {
+        self.inlined_rules.contains(rule_id)
+    }
+
     /// Translate the ABA into base rules / definitions for SAT solving
-    pub fn derive_clauses<Ctx: Context>(&self) -> impl Iterator<Item = Clause> + '_ {
-        theory_helper::<Ctx>(self)
-            .chain(self.derive_loop_breaker::<Ctx>())
-            .chain(self.derive_rule_helper::<Ctx>())
+    ///
+    /// The clauses use the variables of the given copy of the theory, every copy
+    /// can be derived independently of the others.
+    pub fn derive_clauses(&self, ctx: Context) -> impl Iterator<Item = Clause> + '_ {
+        theory_helper(self, ctx)
+            .chain(self.derive_loop_breaker(ctx))
+            .chain(self.derive_rule_helper(ctx))
     }
 
     /// Derive [`Clause`]s to ground the found loops
@@ -64,32 +180,29 @@ impl PreparedAba {
     /// ⋄  -l or LH_i
     /// ```
     /// This will result in `|L| + 1` new clauses per loop.
-    fn derive_loop_breaker<Ctx: Context>(&self) -> impl Iterator<Item = Clause> + '_ {
+    fn derive_loop_breaker(&self, ctx: Context) -> impl Iterator<Item = Clause> + '_ {
         // Iterate over all loops
-        self.loops.iter().enumerate().flat_map(|(loop_id, r#loop)| {
-            // -LH_i or RBA_1 or ... or RBA_n
-            let last_clause = r#loop
-                .support
-                .iter()
-                .map(|el| Ctx::Rule::from(*el).pos())
-                .chain(std::iter::once(Ctx::Loop::from(loop_id).neg()))
-                .collect();
-            // -l or LH_i
-            let head_clauses = r#loop.heads.iter().map(move |head| {
-                Clause::from(vec![
-                    Ctx::Loop::from(loop_id).pos(),
-                    Ctx::Base::from(*head).neg(),
-                ])
-            });
-            // LH_i or -RBA_x
-            let tuple_clauses = r#loop.support.iter().map(move |rule_id| {
-                Clause::from(vec![
-                    Ctx::Rule::from(*rule_id).neg(),
-                    Ctx::Loop::from(loop_id).pos(),
-                ])
-            });
-            tuple_clauses.chain([last_clause]).chain(head_clauses)
-        })
+        self.loops
+            .iter()
+            .enumerate()
+            .flat_map(move |(loop_id, r#loop)| {
+                // -LH_i or RBA_1 or ... or RBA_n
+                let last_clause = r#loop
+                    .support
+                    .iter()
+                    .map(|el| ctx.rule(*el).pos())
+                    .chain(std::iter::once(ctx.r#loop(loop_id).neg()))
+                    .collect();
+                // -l or LH_i
+                let head_clauses = r#loop.heads.iter().map(move |head| {
+                    Clause::from(vec![ctx.r#loop(loop_id).pos(), ctx.atom(*head).neg()])
+                });
+                // LH_i or -RBA_x
+                let tuple_clauses = r#loop.support.iter().map(move |rule_id| {
+                    Clause::from(vec![ctx.rule(*rule_id).neg(), ctx.r#loop(loop_id).pos()])
+                });
+                tuple_clauses.chain([last_clause]).chain(head_clauses)
+            })
     }
 
     /// Derive helper for every rule
@@ -102,26 +215,25 @@ impl PreparedAba {
     ///    RBA_i <=> b_1 and ... and b_n
     /// ⋄  (-RBA_i or b_1) and ... and (-RBA_i or b_n) and (RBA_i or -b_1 or ... or -b_n)
     /// ```
-    /// we will use the `TheoryRuleActive` for `x_R`.
-    fn derive_rule_helper<Ctx: Context>(&self) -> impl Iterator<Item = Clause> + '_ {
+    /// we will use the `TheoryRuleActive` for `x_R`. Rules without a body are always active.
+    /// Inlined rules do not need a helper, see [`PreparedAba::is_inlined`].
+    fn derive_rule_helper(&self, ctx: Context) -> impl Iterator<Item = Clause> + '_ {
         self.rules
             .iter()
             .enumerate()
-            .flat_map(|(rule_id, (_head, body))| {
+            .filter(|(rule_id, _)| !self.is_inlined(*rule_id))
+            .flat_map(move |(rule_id, (_head, body))| {
                 if body.is_empty() {
-                    vec![Clause::from(vec![Ctx::Rule::from(rule_id).neg()])]
+                    vec![Clause::from(vec![ctx.rule(rule_id).pos()])]
                 } else {
                     let last_clause = body
                         .iter()
-                        .map(|el| Ctx::Base::from(*el).neg())
-                        .chain(std::iter::once(Ctx::Rule::from(rule_id).pos()))
+                        .map(|el| ctx.atom(*el).neg())
+                        .chain(std::iter::once(ctx.rule(rule_id).pos()))
                         .collect();
                     body.iter()
                         .map(move |el| {
-                            Clause::from(vec![
-                                Ctx::Rule::from(rule_id).neg(),
-                                Ctx::Base::from(*el).pos(),
-                            ])
+                            Clause::from(vec![ctx.rule(rule_id).neg(), ctx.atom(*el).pos()])
                         })
                         .chain([last_clause])
                         .collect()
@@ -132,59 +244,84 @@ impl PreparedAba {
 
 /// Filtered list of rules
 ///
-/// Iterates over all rules, marking reachable elements until
-/// no additional rule can be applied. Then removes every
+/// Marks all assumptions as reachable and propagates reachability through
+/// the rules, counting the unreachable atoms of every body. Then removes every
 /// rule that contains any unreachable atom and returns the rest
 #[cfg_attr(
     feature = "timing",
     fun_time::fun_time(message = "Triming unnecessary rules from ABA", reporting = "log")
 )]
 fn trim_unreachable_rules(aba: &mut Aba) {
-    // Begin with all assumptions marked as reachable
-    let mut reachable: HashSet<_> = aba.assumptions().cloned().collect();
+    let index = AbaIndex::new(aba);
+    let mut reachable = BitSet::with_capacity(index.len());
+    // Number of body atoms per rule that are not yet reachable
+    let mut missing: Vec<_> = aba.rules.iter().map(|(_, body)| body.len()).collect();
+    // Begin with all assumptions and facts marked as reachable
+    let mut queue: Vec<Num> = aba
+        .assumptions()
+        .cloned()
+        .chain(
+            aba.rules
+                .iter()
+                .filter(|(_, body)| body.is_empty())
+                .map(|(head, _)| *head),
+        )
+        .collect();
     // Calculate all reachable elements
-    loop {
-        let mut marked_any = false;
-        for (head, body) in &aba.rules {
-            if reachable.contains(head) {
-                continue;
-            }
-            if body.iter().all(|atom| reachable.contains(atom)) {
-                marked_any = true;
-                reachable.insert(*head);
-            }
+    while let Some(atom) = queue.pop() {
+        let position = index.position(&atom).unwrap();
+        if !reachable.insert(position) {
+            continue;
         }
-        if !marked_any {
-            break;
+        for rule_id in index.rules_with_body(&atom) {
+            missing[*rule_id] -= 1;
+            if missing[*rule_id] == 0 {
+                queue.push(aba.rules[*rule_id].0);
+            }
         }
     }
+    let is_reachable = |atom: &Num| reachable.contains(index.position(atom).unwrap());
     // Remove all rules that contain any unreachable atom
     aba.rules.retain(|(head, body)| {
         // Both the head and all elements from the body must be reachable
-        reachable.contains(head) && body.iter().all(|atom| reachable.contains(atom))
+        is_reachable(head) && body.iter().all(is_reachable)
     });
 }
 
+/// Indices of all rules that are the only rule for their head
+fn single_body_rules(index: &AbaIndex) -> BitSet {
+    index
+        .atoms()
+        .iter()
+        .filter_map(|atom| match index.rules_with_head(atom) {
+            [rule_id] => Some(*rule_id),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Rules that contain their own head in the body
 /// can never be activated, just drop them
 fn trim_trivial_cycles(aba: &mut Aba) {
     aba.rules.retain(|(head, body)| !body.contains(head))
 }
 
-fn calculate_loops_and_their_support(
-    aba: &Aba,
+fn calculate_loops_and_their_support<'a>(
+    aba: &'a Aba,
+    index: &'a AbaIndex,
     max_loops: Option<usize>,
-) -> impl Iterator<Item = Loop> + '_ {
-    Loops::of(aba, max_loops).map(move |l| {
+    deadline: Option<Instant>,
+    cancel: &CancellationToken,
+) -> impl Iterator<Item = Loop> + 'a {
+    Loops::until(aba, max_loops, deadline, cancel).map(move |l| {
         // Relevant rules are those that contain only elements from outside the loop
         // All other rules cannot influence the value of the loop
-        let support = aba
-            .rules
+        let support = l
+            .heads
             .iter()
-            .enumerate()
-            .filter(|(_rule_id, (head, _body))| l.heads.contains(head))
-            .filter(|(_rule_id, (_head, body))| body.is_disjoint(&l.heads))
-            .map(|(rule_id, _)| rule_id)
+            .flat_map(|head| index.rules_with_head(head))
+            .filter(|rule_id| aba.rules[**rule_id].1.is_disjoint(&l.heads))
+            .cloned()
             .collect();
         Loop {
             heads: l.heads,
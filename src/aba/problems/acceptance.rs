@@ -0,0 +1,328 @@
+//! Credulous and skeptical acceptance of all assumptions at once
+//!
+//! Instead of one query per assumption, a single solver is asked repeatedly. Every
+//! extension found marks its assumptions as credulously accepted and all other
+//! assumptions as not skeptically accepted. Only the assumptions that are still
+//! undecided need further solver calls, similar to backbone algorithms.
+use std::collections::HashSet;
+
+use crate::{
+    aba::{grounded::Grounded, prepared::PreparedAba, Aba, Context, Num},
+    clauses::{Clause, ClauseSimplification},
+    error::Result,
+    literal::{lits::Candidate, IntoLiteral},
+    mapper::Mapper,
+    sat::Backend,
+};
+
+use super::{
+    add_clauses,
+    admissibility::initial_admissibility_clauses,
+    grounded_clauses,
+    limits::{Limits, Watchdog},
+    prepare,
+    stable::initial_stable_clauses,
+    Config, Propagation, DEFAULT_CONTEXTS,
+};
+
+/// Acceptance status of all assumptions under one semantics
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Acceptance {
+    /// Whether the semantics has any extension at all
+    pub exists: bool,
+    /// Assumptions contained in some extension
+    pub credulous: HashSet<Num>,
+    /// Assumptions contained in every extension
+    pub skeptical: HashSet<Num>,
+    /// Extensions found by the solver that decided the acceptance, admissible
+    /// sets for the complete semantics
+    pub witnesses: Vec<HashSet<Num>>,
+}
+
+/// Acceptance of all assumptions under the complete semantics
+///
+/// The skeptically accepted assumptions are the grounded extension, the
+/// credulously accepted ones are those of some admissible extension.
+pub fn complete_acceptance(aba: Aba, config: &Config) -> Result<Acceptance> {
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    accept_complete(&aba, config)
+}
+
+/// Acceptance of all assumptions under the stable semantics
+///
+/// Without any stable extension, no assumption is credulously and every
+/// assumption is skeptically accepted.
+pub fn stable_acceptance(aba: Aba, config: &Config) -> Result<Acceptance> {
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    accept_stable(&aba, config)
+}
+
+/// Acceptance of all assumptions under the preferred semantics
+///
+/// Admissible extensions found are grown into preferred ones, which are blocked
+/// afterwards. To decide the skeptical acceptance of `a`, admissible extensions
+/// without `a` that are not contained in a known preferred extension are searched.
+pub fn preferred_acceptance(aba: Aba, config: &Config) -> Result<Acceptance> {
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    accept_preferred(&aba, config)
+}
+
+/// See [`complete_acceptance`]
+pub(super) fn accept_complete(aba: &PreparedAba, config: &Config) -> Result<Acceptance> {
+    let grounded = Grounded::of(aba, aba.index());
+    let mut solver = Extensions::admissible(aba, config)?;
+    solver.credulous(config.shortcuts.then_some(&grounded))?;
+    Ok(Acceptance {
+        exists: true,
+        credulous: solver.credulous,
+        skeptical: grounded.accepted,
+        witnesses: solver.witnesses,
+    })
+}
+
+/// See [`stable_acceptance`]
+pub(super) fn accept_stable(aba: &PreparedAba, config: &Config) -> Result<Acceptance> {
+    let mut solver = Extensions::new(
+        aba,
+        config,
+        1,
+        Propagation::Complete,
+        initial_stable_clauses(aba),
+    )?;
+    solver.narrow = true;
+    let Some(first) = solver.find(&[], &[])? else {
+        return Ok(Acceptance {
+            exists: false,
+            credulous: HashSet::new(),
+            skeptical: aba.assumptions().cloned().collect(),
+            witnesses: vec![],
+        });
+    };
+    solver.record(&first);
+    solver.credulous(None)?;
+    for assumption in sorted(solver.skeptical.clone()) {
+        if !solver.skeptical.contains(&assumption) {
+            continue;
+        }
+        if let Some(extension) = solver.find(&[], &[assumption])? {
+            solver.record(&extension);
+        }
+    }
+    Ok(solver.into_acceptance())
+}
+
+/// See [`preferred_acceptance`]
+pub(super) fn accept_preferred(aba: &PreparedAba, config: &Config) -> Result<Acceptance> {
+    let grounded = Grounded::of(aba, aba.index());
+    let mut solver = Extensions::admissible(aba, config)?;
+    // There is at least one preferred extension, but no admissible set is blocked yet
+    let first = solver.find(&[], &[])?.expect("the empty set is admissible");
+    solver.record_preferred(first)?;
+    solver.credulous(config.shortcuts.then_some(&grounded))?;
+    for assumption in sorted(solver.skeptical.clone()) {
+        // The grounded extension is part of every preferred extension
+        if config.shortcuts && grounded.accepted.contains(&assumption) {
+            continue;
+        }
+        // Every preferred extension found so far contains the assumption
+        while solver.skeptical.contains(&assumption) {
+            match solver.find(&[], &[assumption])? {
+                Some(extension) => solver.record_preferred(extension)?,
+                None => break,
+            }
+        }
+    }
+    Ok(solver.into_acceptance())
+}
+
+/// A solver searching extensions, with the acceptance found so far
+struct Extensions<'a> {
+    aba: &'a PreparedAba,
+    map: Mapper,
+    sat: Box<dyn Backend>,
+    watchdog: Watchdog,
+    limits: Limits,
+    simplification: ClauseSimplification,
+    /// A clause blocking everything was added, there are no further extensions
+    exhausted: bool,
+    /// The solutions are extensions of the semantics, not just admissible sets,
+    /// and restrict the skeptical acceptance as well
+    narrow: bool,
+    credulous: HashSet<Num>,
+    /// Assumptions contained in all extensions found so far
+    skeptical: HashSet<Num>,
+    witnesses: Vec<HashSet<Num>>,
+}
+
+impl<'a> Extensions<'a> {
+    fn new<I: Iterator<Item = Clause>>(
+        aba: &'a PreparedAba,
+        config: &Config,
+        contexts: usize,
+        propagation: Propagation,
+        clauses: I,
+    ) -> Result<Self> {
+        let map = Mapper::new(aba).with_contexts(contexts).checked()?;
+        let mut sat = config.backend.create();
+        let watchdog = Watchdog::new(config.limits.solving_deadline(), &config.cancel);
+        watchdog.watch(sat.as_mut(), &config.limits);
+        let clauses = aba
+            .derive_clauses(Context::CANDIDATE)
+            .chain(grounded_clauses(aba, config, propagation))
+            .chain(clauses);
+        add_clauses(sat.as_mut(), map.as_raw_iter(clauses), config.clauses);
+        Ok(Extensions {
+            aba,
+            map,
+            sat,
+            watchdog,
+            limits: config.limits.clone(),
+            simplification: config.clauses,
+            exhausted: false,
+            narrow: false,
+            credulous: HashSet::new(),
+            skeptical: aba.assumptions().cloned().collect(),
+            witnesses: vec![],
+        })
+    }
+
+    fn admissible(aba: &'a PreparedAba, config: &Config) -> Result<Self> {
+        Extensions::new(
+            aba,
+            config,
+            DEFAULT_CONTEXTS,
+            Propagation::Defeated,
+            initial_admissibility_clauses(aba),
+        )
+    }
+
+    /// Find an extension containing `inside` but nothing of `outside`
+    fn find(&mut self, inside: &[Num], outside: &[Num]) -> Result<Option<HashSet<Num>>> {
+        if self.exhausted {
+            return Ok(None);
+        }
+        let assumptions = inside
+            .iter()
+            .map(|assumption| Candidate::from(*assumption).pos())
+            .chain(
+                outside
+                    .iter()
+                    .map(|assumption| Candidate::from(*assumption).neg()),
+            )
+            .map(|literal| self.map.as_raw(&literal))
+            .collect::<Vec<_>>();
+        let sat_result = self
+            .sat
+            .solve_with(&assumptions)
+            .ok_or_else(|| self.watchdog.interruption(self.sat.as_mut(), &self.limits))?;
+        if !sat_result {
+            return Ok(None);
+        }
+        let extension = self
+            .aba
+            .assumptions()
+            .filter(|assumption| {
+                let raw = self.map.as_raw(&Candidate::from(**assumption).pos());
+                self.sat.value(raw) == Some(true)
+            })
+            .cloned()
+            .collect();
+        Ok(Some(extension))
+    }
+
+    /// Update the acceptance with an extension of the semantics
+    fn record(&mut self, extension: &HashSet<Num>) {
+        self.credulous.extend(extension);
+        self.skeptical
+            .retain(|assumption| extension.contains(assumption));
+        self.witnesses.push(extension.clone());
+    }
+
+    /// Decide the credulous acceptance of all assumptions not yet accepted
+    ///
+    /// Assumptions accepted by the `grounded` extension are accepted, those it
+    /// defeats are not, neither needs a solver call. Every extension found marks
+    /// all of its assumptions as credulously accepted.
+    fn credulous(&mut self, grounded: Option<&Grounded>) -> Result {
+        if let Some(grounded) = grounded {
+            self.credulous.extend(&grounded.accepted);
+        }
+        let open = sorted(self.aba.assumptions().cloned().collect())
+            .into_iter()
+            .filter(|assumption| {
+                !grounded.is_some_and(|grounded| grounded.defeated.contains(assumption))
+            })
+            .collect::<Vec<_>>();
+        for assumption in open {
+            if self.credulous.contains(&assumption) {
+                continue;
+            }
+            match self.find(&[assumption], &[])? {
+                Some(extension) if self.narrow => self.record(&extension),
+                Some(extension) => {
+                    self.credulous.extend(&extension);
+                    self.witnesses.push(extension);
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Grow an admissible `extension` into a preferred one, record and block it
+    ///
+    /// The blocking clause excludes all subsets of the preferred extension. These
+    /// are never preferred themselves and all their assumptions are accepted
+    /// credulously already.
+    fn record_preferred(&mut self, mut extension: HashSet<Num>) -> Result {
+        for assumption in sorted(self.aba.assumptions().cloned().collect()) {
+            if extension.contains(&assumption) {
+                continue;
+            }
+            let inside = extension
+                .iter()
+                .cloned()
+                .chain([assumption])
+                .collect::<Vec<_>>();
+            if let Some(larger) = self.find(&inside, &[])? {
+                extension = larger;
+            }
+        }
+        self.record(&extension);
+        let blocking: Clause = self
+            .aba
+            .assumptions()
+            .filter(|assumption| !extension.contains(assumption))
+            .map(|assumption| Candidate::from(*assumption).pos())
+            .collect();
+        if blocking.is_empty() {
+            self.exhausted = true;
+        } else {
+            add_clauses(
+                self.sat.as_mut(),
+                self.map.as_raw_iter([blocking]),
+                self.simplification,
+            );
+        }
+        Ok(())
+    }
+
+    /// Only called after an extension was found
+    fn into_acceptance(self) -> Acceptance {
+        Acceptance {
+            exists: true,
+            credulous: self.credulous,
+            skeptical: self.skeptical,
+            witnesses: self.witnesses,
+        }
+    }
+}
+
+fn sorted(set: HashSet<Num>) -> Vec<Num> {
+    let mut list: Vec<_> = set.into_iter().collect();
+    list.sort_unstable();
+    list
+}
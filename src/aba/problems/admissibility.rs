@@ -2,8 +2,13 @@
 use std::collections::HashSet;
 
 use crate::{
-    aba::{prepared::PreparedAba, Aba, Num},
-    clauses::{Clause, ClauseList},
+    aba::{
+        grounded::{derivable_from, Grounded},
+        prepared::PreparedAba,
+        statistics::Shortcut,
+        Context, Num,
+    },
+    clauses::Clause,
     error::Error,
     literal::{
         lits::{Attacker, Candidate},
@@ -12,35 +17,34 @@ use crate::{
     Result,
 };
 
-use super::{LoopControl, MultishotProblem, Problem, SolverState};
+use super::{LoopControl, MultishotProblem, Problem, Propagation, SolverState};
 
-/// Compute all admissible extensions for an [`Aba`]
+/// Compute all admissible extensions for an [`Aba`](crate::aba::Aba)
 #[derive(Default, Debug)]
 pub struct EnumerateAdmissibleExtensions {
     found: Vec<HashSet<Num>>,
 }
 
-/// Sample an admissible extensions from an [`Aba`].
+/// Sample an admissible extensions from an [`Aba`](crate::aba::Aba).
 /// Will only return the empty set if no other extension is found
 #[derive(Debug, Default)]
 pub struct SampleAdmissibleExtension;
 
-/// Verify wether `assumptions` is an admissible extension of an [`Aba`]
+/// Verify wether `assumptions` is an admissible extension of an [`Aba`](crate::aba::Aba)
 pub struct VerifyAdmissibleExtension {
     pub assumptions: HashSet<Num>,
 }
 
-/// Decide whether `assumption` is credulously admissible in an [`Aba`]
+/// Decide whether `assumption` is credulously admissible in an [`Aba`](crate::aba::Aba)
 pub struct DecideCredulousAdmissibility {
     pub element: Num,
 }
 
-pub fn initial_admissibility_clauses(aba: &PreparedAba) -> ClauseList {
-    let mut clauses = vec![];
+pub fn initial_admissibility_clauses(aba: &PreparedAba) -> impl Iterator<Item = Clause> + '_ {
     // Create inference for the problem set
-    aba.derive_clauses::<Attacker>().collect_into(&mut clauses);
+    let attacker_inference = aba.derive_clauses(Context::ATTACKER);
     // Attack the inference of the aba, if an attack exists
-    for (assumption, inverse) in &aba.inverses {
+    let attacks = aba.inverses.iter().flat_map(|(assumption, inverse)| {
         [
             // For any assumption `a` and it's inverse `b`:
             //   b not in th(Candidate) <=> a in th(Attacker)
@@ -67,10 +71,66 @@ pub fn initial_admissibility_clauses(aba: &PreparedAba) -> ClauseList {
                 Candidate::from(*inverse).neg(),
             ]),
         ]
-        .into_iter()
-        .collect_into(&mut clauses);
+    });
+    attacker_inference.chain(attacks)
+}
+
+/// Prevent the empty set from being picked as a solution
+fn no_empty_set(aba: &PreparedAba) -> Clause {
+    aba.inverses
+        .keys()
+        .map(|assumption| Candidate::from(*assumption).pos())
+        .collect()
+}
+
+/// Prevent the set `just_found` from being picked again
+///
+/// If we've found {a, c, d} in the last iteration, prevent it from being picked again.
+/// Assuming a..=f are our assumptions: {-a, b, -c, -d, e, f} must be true
+pub fn blocking_clause(aba: &PreparedAba, just_found: &HashSet<Num>) -> Clause {
+    aba.assumptions()
+        .map(|assumption| {
+            if just_found.contains(assumption) {
+                Candidate::from(*assumption).neg()
+            } else {
+                Candidate::from(*assumption).pos()
+            }
+        })
+        .collect()
+}
+
+/// Decide the credulous acceptance of `element` using polynomial rules only
+///
+/// An atom is credulously accepted under the admissible semantics iff it is under
+/// the complete semantics, this is used by both.
+pub fn credulous_shortcut(aba: &PreparedAba, element: Num) -> Option<(Shortcut, bool)> {
+    let Some(element) = aba.representative(element) else {
+        return Some((Shortcut::Underivable, false));
+    };
+    if let Some(inverse) = aba.inverses.get(&element) {
+        let rules = aba.index().rules_with_head(inverse);
+        if rules.is_empty() && !aba.contains_assumption(inverse) {
+            return Some((Shortcut::Unattackable, true));
+        }
+        if rules.iter().any(|rule_id| aba.rules[*rule_id].1.is_empty()) {
+            return Some((Shortcut::ContraryIsFact, false));
+        }
     }
-    clauses
+    let grounded = Grounded::of(aba, aba.index());
+    if grounded.defeated.contains(&element) {
+        return Some((Shortcut::Defeated, false));
+    }
+    if derivable_from(aba, aba.index(), &grounded.accepted).contains(&element) {
+        return Some((Shortcut::Grounded, true));
+    }
+    // Defeated assumptions are never part of an admissible extension
+    let possible = aba
+        .assumptions()
+        .filter(|assumption| !grounded.defeated.contains(assumption));
+    if !derivable_from(aba, aba.index(), possible).contains(&element) {
+        return Some((Shortcut::Underivable, false));
+    }
+    None
 }
 
 fn construct_found_set(state: SolverState<'_>) -> HashSet<Num> {
@@ -91,16 +151,8 @@ fn construct_found_set(state: SolverState<'_>) -> HashSet<Num> {
 impl Problem for SampleAdmissibleExtension {
     type Output = HashSet<Num>;
 
-    fn additional_clauses(&self, aba: &PreparedAba) -> ClauseList {
-        let mut clauses = initial_admissibility_clauses(aba);
-        // Prevent the empty set
-        let no_empty_set: Clause = aba
-            .inverses
-            .keys()
-            .map(|assumption| Candidate::from(*assumption).pos())
-            .collect();
-        clauses.push(no_empty_set);
-        clauses
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause> {
+        initial_admissibility_clauses(aba).chain([no_empty_set(aba)])
     }
 
     fn construct_output(self, state: SolverState<'_>) -> Self::Output {
@@ -110,42 +162,33 @@ impl Problem for SampleAdmissibleExtension {
             HashSet::new()
         }
     }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Defeated
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![])
+    }
 }
 
 impl MultishotProblem for EnumerateAdmissibleExtensions {
     type Output = Vec<HashSet<Num>>;
 
-    fn additional_clauses(&self, aba: &PreparedAba, iteration: usize) -> ClauseList {
-        match iteration {
-            0 => {
-                let mut clauses = initial_admissibility_clauses(aba);
-                // Prevent the empty set
-                let no_empty_set: Clause = aba
-                    .inverses
-                    .keys()
-                    .map(|assumption| Candidate::from(*assumption).pos())
-                    .collect();
-                clauses.push(no_empty_set);
-                clauses
-            }
-            idx => {
-                // If we've found {a, c, d} in the last iteration, prevent it from being picked again
-                // Assuming a..=f are our assumptions:
-                //   {-a, b, -c, -d, e, f} must be true
-                let just_found = &self.found[idx - 1];
-                let new_clause = aba
-                    .assumptions()
-                    .map(|assumption| {
-                        if just_found.contains(assumption) {
-                            Candidate::from(*assumption).neg()
-                        } else {
-                            Candidate::from(*assumption).pos()
-                        }
-                    })
-                    .collect();
-                vec![new_clause]
-            }
-        }
+    fn additional_clauses(
+        &self,
+        aba: &PreparedAba,
+        iteration: usize,
+    ) -> impl Iterator<Item = Clause> {
+        let initial = match iteration {
+            0 => Some(initial_admissibility_clauses(aba).chain([no_empty_set(aba)])),
+            _ => None,
+        };
+        let blocking = match iteration {
+            0 => None,
+            idx => Some(blocking_clause(aba, &self.found[idx - 1])),
+        };
+        initial.into_iter().flatten().chain(blocking)
     }
 
     fn feedback(&mut self, state: SolverState<'_>, _iteration: usize) -> LoopControl {
@@ -170,42 +213,47 @@ impl MultishotProblem for EnumerateAdmissibleExtensions {
         LoopControl::Continue
     }
 
-    fn construct_output(
-        mut self,
-        _state: SolverState<'_>,
-        _total_iterations: usize,
-    ) -> Self::Output {
+    fn construct_output(self, state: SolverState<'_>, _total_iterations: usize) -> Self::Output {
+        // Add the sets excluded by symmetry breaking
+        let mut found = state.symmetries.expand(self.found);
         // Re-Add the empty set
-        self.found.push(HashSet::new());
-        self.found
+        found.push(HashSet::new());
+        found
             .into_iter()
             .map(|set| set.into_iter().collect())
             .collect()
     }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Defeated
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![])
+    }
 }
 
 impl Problem for VerifyAdmissibleExtension {
     type Output = bool;
 
-    fn additional_clauses(&self, aba: &PreparedAba) -> crate::clauses::ClauseList {
-        let mut clauses = initial_admissibility_clauses(aba);
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause> {
         // Force inference on all members of the set
-        for assumption in aba.assumptions() {
+        let members = aba.assumptions().map(|assumption| {
             let inf = Candidate::from(*assumption);
             if self.assumptions.contains(assumption) {
-                clauses.push(Clause::from(vec![inf.pos()]))
+                Clause::from(vec![inf.pos()])
             } else {
-                clauses.push(Clause::from(vec![inf.neg()]))
+                Clause::from(vec![inf.neg()])
             }
-        }
-        clauses
+        });
+        initial_admissibility_clauses(aba).chain(members)
     }
 
     fn construct_output(self, state: SolverState<'_>) -> Self::Output {
         state.sat_result
     }
 
-    fn check(&self, aba: &Aba) -> Result {
+    fn check(&self, aba: &PreparedAba) -> Result {
         // Make sure that every assumption is part of the ABA
         match self
             .assumptions
@@ -218,22 +266,25 @@ impl Problem for VerifyAdmissibleExtension {
             None => Ok(()),
         }
     }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Defeated
+    }
 }
 
 impl Problem for DecideCredulousAdmissibility {
     type Output = bool;
 
-    fn additional_clauses(&self, aba: &PreparedAba) -> ClauseList {
-        let mut clauses = initial_admissibility_clauses(aba);
-        clauses.push(Clause::from(vec![Candidate::from(self.element).pos()]));
-        clauses
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause> {
+        initial_admissibility_clauses(aba)
+            .chain([Clause::from(vec![Candidate::from(self.element).pos()])])
     }
 
     fn construct_output(self, state: SolverState<'_>) -> Self::Output {
         state.sat_result
     }
 
-    fn check(&self, aba: &Aba) -> Result {
+    fn check(&self, aba: &PreparedAba) -> Result {
         if aba.contains_assumption(&self.element) {
             Ok(())
         } else {
@@ -243,4 +294,20 @@ impl Problem for DecideCredulousAdmissibility {
             )))
         }
     }
+
+    fn shortcut(&self, aba: &PreparedAba) -> Option<(Shortcut, Self::Output)> {
+        credulous_shortcut(aba, self.element)
+    }
+
+    fn slice_query(&self) -> Option<Num> {
+        Some(self.element)
+    }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Defeated
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![self.element])
+    }
 }
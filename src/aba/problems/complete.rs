@@ -1,8 +1,8 @@
 use std::collections::HashSet;
 
 use crate::{
-    aba::{prepared::PreparedAba, Aba, Num},
-    clauses::{Clause, ClauseList},
+    aba::{prepared::PreparedAba, statistics::Shortcut, Num},
+    clauses::Clause,
     error::Error,
     literal::{
         lits::{Attacker, Candidate},
@@ -12,8 +12,8 @@ use crate::{
 };
 
 use super::{
-    admissibility::initial_admissibility_clauses, LoopControl, MultishotProblem, Problem,
-    SolverState,
+    admissibility::{blocking_clause, credulous_shortcut, initial_admissibility_clauses},
+    LoopControl, MultishotProblem, Problem, Propagation, SolverState,
 };
 
 #[derive(Debug, Default)]
@@ -21,50 +21,42 @@ pub struct EnumerateCompleteExtensions {
     found: Vec<HashSet<Num>>,
 }
 
-/// Decide whether `assumption` is credulously complete in an [`Aba`]
+/// Decide whether `assumption` is credulously complete in an [`Aba`](crate::aba::Aba)
 pub struct DecideCredulousComplete {
     pub element: Num,
 }
 
-fn initial_complete_clauses(aba: &PreparedAba) -> ClauseList {
-    // Take everything from admissibility
-    let mut clauses = initial_admissibility_clauses(aba);
+fn initial_complete_clauses(aba: &PreparedAba) -> impl Iterator<Item = Clause> + '_ {
     // Additional complete logic
-    for (assumption, inverse) in &aba.inverses {
+    let complete = aba.inverses.iter().map(|(assumption, inverse)| {
         // For any assumption `a` and it's inverse `b`:
         //   b not in th(Attacker) => a in th(Candidate)
-        clauses.push(Clause::from(vec![
+        Clause::from(vec![
             Attacker::from(*inverse).pos(),
             Candidate::from(*assumption).pos(),
-        ]));
-    }
-    clauses
+        ])
+    });
+    // Take everything from admissibility
+    initial_admissibility_clauses(aba).chain(complete)
 }
 
 impl MultishotProblem for EnumerateCompleteExtensions {
     type Output = Vec<HashSet<Num>>;
 
-    fn additional_clauses(&self, aba: &PreparedAba, iteration: usize) -> ClauseList {
-        match iteration {
-            0 => initial_complete_clauses(aba),
-            idx => {
-                // If we've found {a, c, d} in the last iteration, prevent it from being picked again
-                // Assuming a..=f are our assumptions:
-                //   {-a, b, -c, -d, e, f} must be true
-                let just_found = &self.found[idx - 1];
-                let new_clause = aba
-                    .assumptions()
-                    .map(|assumption| {
-                        if just_found.contains(assumption) {
-                            Candidate::from(*assumption).neg()
-                        } else {
-                            Candidate::from(*assumption).pos()
-                        }
-                    })
-                    .collect();
-                vec![new_clause]
-            }
-        }
+    fn additional_clauses(
+        &self,
+        aba: &PreparedAba,
+        iteration: usize,
+    ) -> impl Iterator<Item = Clause> {
+        let initial = match iteration {
+            0 => Some(initial_complete_clauses(aba)),
+            _ => None,
+        };
+        let blocking = match iteration {
+            0 => None,
+            idx => Some(blocking_clause(aba, &self.found[idx - 1])),
+        };
+        initial.into_iter().flatten().chain(blocking)
     }
 
     fn feedback(&mut self, state: SolverState<'_>, _iteration: usize) -> LoopControl {
@@ -89,25 +81,37 @@ impl MultishotProblem for EnumerateCompleteExtensions {
         LoopControl::Continue
     }
 
-    fn construct_output(self, _state: SolverState<'_>, _total_iterations: usize) -> Self::Output {
-        self.found
+    fn construct_output(self, state: SolverState<'_>, _total_iterations: usize) -> Self::Output {
+        // Add the sets excluded by symmetry breaking
+        state.symmetries.expand(self.found)
+    }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Complete
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![])
     }
 }
 
 impl Problem for DecideCredulousComplete {
     type Output = bool;
 
-    fn additional_clauses(&self, aba: &PreparedAba) -> ClauseList {
-        let mut clauses = initial_complete_clauses(aba);
-        clauses.push(Clause::from(vec![Candidate::from(self.element).pos()]));
-        clauses
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause> {
+        // An element without representative can never be derived, use the empty clause
+        let query = match aba.representative(self.element) {
+            Some(element) => Clause::from(vec![Candidate::from(element).pos()]),
+            None => Clause::from(vec![]),
+        };
+        initial_complete_clauses(aba).chain([query])
     }
 
     fn construct_output(self, state: SolverState<'_>) -> Self::Output {
         state.sat_result
     }
 
-    fn check(&self, aba: &Aba) -> Result {
+    fn check(&self, aba: &PreparedAba) -> Result {
         if aba.contains_atom(&self.element) {
             Ok(())
         } else {
@@ -117,4 +121,20 @@ impl Problem for DecideCredulousComplete {
             )))
         }
     }
+
+    fn shortcut(&self, aba: &PreparedAba) -> Option<(Shortcut, Self::Output)> {
+        credulous_shortcut(aba, self.element)
+    }
+
+    fn slice_query(&self) -> Option<Num> {
+        Some(self.element)
+    }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Complete
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![self.element])
+    }
 }
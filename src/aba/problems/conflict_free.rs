@@ -1,8 +1,8 @@
 use std::collections::HashSet;
 
 use crate::{
-    aba::{prepared::PreparedAba, Aba, Num},
-    clauses::{Clause, ClauseList},
+    aba::{prepared::PreparedAba, Num},
+    clauses::Clause,
     error::Error,
     literal::{lits::Candidate, IntoLiteral},
     Result,
@@ -17,31 +17,30 @@ pub struct ConflictFreeness {
 impl Problem for ConflictFreeness {
     type Output = bool;
 
-    fn additional_clauses(&self, aba: &PreparedAba) -> ClauseList {
-        let mut clauses = vec![];
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause> {
         // Make sure that every assumption in our problem is inferred and every other not
-        for assumption in aba.assumptions() {
+        let members = aba.assumptions().map(|assumption| {
             let theory = Candidate::from(*assumption);
             if self.assumptions.contains(assumption) {
-                clauses.push(vec![theory.pos()].into())
+                vec![theory.pos()].into()
             } else {
-                clauses.push(vec![theory.neg()].into())
+                vec![theory.neg()].into()
             }
-        }
-        for (assumption, inverse) in &aba.inverses {
-            clauses.push(Clause::from(vec![
+        });
+        let conflicts = aba.inverses.iter().map(|(assumption, inverse)| {
+            Clause::from(vec![
                 Candidate::from(*assumption).neg(),
                 Candidate::from(*inverse).neg(),
-            ]));
-        }
-        clauses
+            ])
+        });
+        members.chain(conflicts)
     }
 
     fn construct_output(self, state: SolverState<'_>) -> Self::Output {
         state.sat_result
     }
 
-    fn check(&self, aba: &Aba) -> Result {
+    fn check(&self, aba: &PreparedAba) -> Result {
         // Make sure that every assumption is part of the ABA
         match self
             .assumptions
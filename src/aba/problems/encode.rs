@@ -0,0 +1,69 @@
+//! Export the encoding of a problem instead of solving it
+//!
+//! The clauses are built like [`solve`](super::solve) and
+//! [`multishot_solve`](super::multishot_solve) build them, including the slicing,
+//! the grounded propagation, symmetry breaking and clause simplification enabled
+//! in the [`Config`]. Shortcuts are never taken.
+use std::io::Write;
+
+use crate::{
+    aba::Aba, clauses::RawClause, error::Result, mapper::Mapper, sat::dimacs::write_dimacs,
+};
+
+use super::{
+    base_clauses, find_symmetries, mapper, prepare, simplified, sliced, Config, MultishotProblem,
+    Problem,
+};
+
+/// Clauses handed to the solver together with the meaning of their variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoding {
+    pub map: Mapper,
+    pub clauses: Vec<RawClause>,
+}
+
+/// The clauses of the single solver call of [`solve`](super::solve)
+pub fn encode<P: Problem>(problem: &P, aba: Aba, config: &Config) -> Result<Encoding> {
+    let aba = prepare(sliced(aba, problem.slice_query()), config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    problem.check(&aba)?;
+    let symmetries = find_symmetries(&aba, config, problem.symmetry_breaking());
+    let map = mapper(&aba, problem.contexts(), &symmetries)?;
+    let clauses = base_clauses(&aba, config, problem.grounded_propagation(), &symmetries)
+        .chain(problem.additional_clauses(&aba));
+    let clauses = simplified(map.as_raw_iter(clauses), config.clauses).collect();
+    Ok(Encoding { map, clauses })
+}
+
+/// The clauses of the first solver call of [`multishot_solve`](super::multishot_solve)
+///
+/// Later calls only add the clauses the problem derives from the previous solutions.
+pub fn encode_multishot<P: MultishotProblem>(
+    problem: &P,
+    aba: Aba,
+    config: &Config,
+) -> Result<Encoding> {
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    problem.check(&aba)?;
+    let symmetries = find_symmetries(&aba, config, problem.symmetry_breaking());
+    let map = mapper(&aba, problem.contexts(), &symmetries)?;
+    let clauses = base_clauses(&aba, config, problem.grounded_propagation(), &symmetries)
+        .chain(problem.additional_clauses(&aba, 0));
+    let clauses = simplified(map.as_raw_iter(clauses), config.clauses).collect();
+    Ok(Encoding { map, clauses })
+}
+
+impl Encoding {
+    /// The clauses in DIMACS format, preceded by a comment per variable like
+    /// `c 17 Attacker(4)`
+    pub fn to_dimacs(&self) -> Result<String> {
+        let mut dimacs = vec![];
+        for (variable, literal) in self.map.variables() {
+            writeln!(dimacs, "c {variable} {literal}")?;
+        }
+        write_dimacs(&mut dimacs, self.map.max_variable(), &self.clauses, &[])?;
+        let dimacs = String::from_utf8(dimacs).expect("DIMACS is written as ASCII");
+        Ok(dimacs.trim_end().to_owned())
+    }
+}
@@ -0,0 +1,113 @@
+//! Time and conflict limits for solving problems
+//!
+//! Time limits of the solver calls are enforced by a [`Watchdog`]. The solver
+//! checks a token linked to the [`CancellationToken`] of the problem, a timer
+//! thread cancels it once the deadline passed.
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use crate::{cancel::CancellationToken, error::Error, sat::Backend};
+
+/// Resources available to a problem, see [`Config::limits`](super::Config::limits)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Point in time all work has to be finished by
+    pub deadline: Option<Instant>,
+    /// Time to search loops while preparing a framework, the loops found until
+    /// then are used
+    pub loops: Option<Duration>,
+    /// Time for all solver calls of a single problem
+    pub solving: Option<Duration>,
+    /// Maximum number of conflicts of a single solver call
+    pub conflicts: Option<u32>,
+}
+
+impl Limits {
+    /// Deadline of a loop search starting now
+    pub fn loop_deadline(&self) -> Option<Instant> {
+        earliest(self.deadline, self.loops.map(|time| Instant::now() + time))
+    }
+
+    /// Deadline of the solver calls of a problem whose solving starts now
+    pub fn solving_deadline(&self) -> Option<Instant> {
+        earliest(
+            self.deadline,
+            self.solving.map(|time| Instant::now() + time),
+        )
+    }
+}
+
+fn earliest(first: Option<Instant>, second: Option<Instant>) -> Option<Instant> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(first.min(second)),
+        (first, second) => first.or(second),
+    }
+}
+
+/// Interrupts the solvers it watches once a deadline passed or the problem was
+/// cancelled
+///
+/// The timer thread only exists if there is a deadline, it ends when the watchdog
+/// is dropped.
+pub struct Watchdog {
+    /// Cancelled together with the problem or once the deadline passed
+    stop: CancellationToken,
+    cancel: CancellationToken,
+    /// Dropping the sender wakes up the timer thread
+    _timer: Option<mpsc::Sender<()>>,
+}
+
+impl Watchdog {
+    pub fn new(deadline: Option<Instant>, cancel: &CancellationToken) -> Self {
+        let stop = CancellationToken::linked([cancel]);
+        let expired = move || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        // Do not let the first solver call race against the thread
+        if expired() {
+            stop.cancel();
+        }
+        let timer = deadline.filter(|_| !expired()).map(|deadline| {
+            let (sender, receiver) = mpsc::channel::<()>();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let remaining = || deadline.saturating_duration_since(Instant::now());
+                while let Err(mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(remaining())
+                {
+                    if expired() {
+                        log::warn!("Time limit exceeded, interrupting the solver");
+                        stop.cancel();
+                        break;
+                    }
+                }
+            });
+            sender
+        });
+        Watchdog {
+            stop,
+            cancel: cancel.clone(),
+            _timer: timer,
+        }
+    }
+
+    /// Apply the deadline and the conflict limit of `limits` to `sat`
+    pub fn watch(&self, sat: &mut dyn Backend, limits: &Limits) {
+        sat.set_terminate(self.stop.clone());
+        sat.set_conflict_limit(limits.conflicts);
+    }
+
+    /// The error for a solver call of `sat` that returned without an answer
+    pub fn interruption(&self, sat: &mut dyn Backend, limits: &Limits) -> Error {
+        if let Some(why) = sat.take_error() {
+            Error::Solver(why)
+        } else if self.cancel.is_cancelled() {
+            Error::Cancelled
+        } else if self.stop.is_cancelled() {
+            Error::Timeout
+        } else if limits.conflicts.is_some() {
+            Error::ConflictLimit
+        } else {
+            Error::SatCallInterrupted
+        }
+    }
+}
@@ -1,17 +1,121 @@
-use cadical::Solver;
+use std::path::PathBuf;
 
 use crate::{
-    clauses::ClauseList,
+    cancel::CancellationToken,
+    clauses::{simplify_clauses, Clause, ClauseSimplification, RawClause},
     error::{Error, Result},
-    literal::lits::Candidate,
+    literal::{lits::Candidate, IntoLiteral},
     mapper::Mapper,
+    sat::{
+        proof::{write_mapping, Traced},
+        Backend, SolverBackend,
+    },
 };
 
-use super::{prepared::PreparedAba, Aba};
+use self::limits::{Limits, Watchdog};
 
+use super::{
+    grounded::Grounded, prepared::PreparedAba, slice::relevant_slice, statistics::Shortcut,
+    symmetry::Symmetries, Aba, Context, Num,
+};
+
+pub mod acceptance;
 pub mod admissibility;
 pub mod complete;
 pub mod conflict_free;
+pub mod encode;
+pub mod limits;
+pub mod parallel;
+pub mod report;
+pub mod session;
+pub mod split;
+pub mod stable;
+
+/// Options for [`solve`] and [`multishot_solve`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Maximum number of loops to break, see [`Aba::prepare`]
+    pub max_loops: Option<usize>,
+    /// Compute the grounded extension before solving and fix the [`Candidate`]
+    /// literals it determines, see [`Problem::grounded_propagation`]
+    pub grounded: bool,
+    /// Simplify the framework before encoding it, see [`simplify`](crate::aba::simplify)
+    pub simplify: bool,
+    /// Try to answer problems without the SAT solver, see [`Problem::shortcut`]
+    ///
+    /// Off by default, so every problem reaches the encoding. The command line
+    /// enables it unless `--no-shortcuts` or `--proof` is given.
+    pub shortcuts: bool,
+    /// Simplification applied to the clauses before they are handed to the solver
+    pub clauses: ClauseSimplification,
+    /// Break symmetries of the framework, see [`Problem::symmetry_breaking`]
+    pub symmetries: bool,
+    /// SAT solver used to solve the encoded problem
+    pub backend: SolverBackend,
+    /// Time and conflict limits, no limits by default
+    pub limits: Limits,
+    /// Cancels the problem, the loop search ends and solver calls fail with
+    /// [`Error::Cancelled`]
+    pub cancel: CancellationToken,
+    /// Ends the loop search early, solving continues with the loops found so far
+    pub stop_loops: CancellationToken,
+    /// Write a DRAT proof for the solver call of [`solve`] to this path, together
+    /// with the formula and the variable mapping, see [`proof`](crate::sat::proof)
+    pub proof: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_loops: None,
+            grounded: false,
+            simplify: true,
+            shortcuts: false,
+            clauses: ClauseSimplification::default(),
+            symmetries: false,
+            backend: SolverBackend::default(),
+            limits: Limits::default(),
+            cancel: CancellationToken::new(),
+            stop_loops: CancellationToken::new(),
+            proof: None,
+        }
+    }
+}
+
+/// Literals that may be fixed using the grounded extension
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Propagation {
+    /// The solutions are not related to the grounded extension
+    #[default]
+    Disabled,
+    /// Solutions are admissible, assumptions attacked by the grounded extension are out
+    Defeated,
+    /// Solutions are complete, they additionally contain the grounded extension
+    Complete,
+}
+
+/// Contexts used by most problems, [`Context::CANDIDATE`] and [`Context::ATTACKER`]
+pub const DEFAULT_CONTEXTS: usize = 2;
+
+/// Result of [`multishot_solve_partial`]
+#[derive(Debug)]
+pub enum Outcome<T> {
+    /// The problem was solved completely
+    Complete(T),
+    /// A solver call was interrupted, the output is constructed from the
+    /// solutions found until then
+    Partial(T, Error),
+}
+
+impl<T> Outcome<T> {
+    /// The output of a complete solve, the reason of the interruption otherwise
+    pub fn complete(self) -> Result<T> {
+        match self {
+            Outcome::Complete(output) => Ok(output),
+            Outcome::Partial(_, why) => Err(why),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum LoopControl {
@@ -22,58 +126,135 @@ pub enum LoopControl {
 pub struct SolverState<'a> {
     aba: &'a Aba,
     sat_result: bool,
-    solver: &'a Solver,
+    solver: &'a dyn Backend,
     map: &'a Mapper,
+    /// Symmetries broken in the encoding, used to expand the found solutions
+    symmetries: &'a Symmetries,
 }
 
 #[doc(notable_trait)]
 pub trait Problem {
     type Output;
-    fn additional_clauses(&self, aba: &PreparedAba) -> ClauseList;
+    fn additional_clauses(&self, aba: &PreparedAba) -> impl Iterator<Item = Clause>;
     fn construct_output(self, state: SolverState<'_>) -> Self::Output;
 
-    fn check(&self, _aba: &Aba) -> Result {
+    fn check(&self, _aba: &PreparedAba) -> Result {
         Ok(())
     }
+
+    /// Answer the problem without calling the SAT solver, if a cheap rule applies
+    ///
+    /// Called after [`Problem::check`] succeeded. The used [`Shortcut`] is reported
+    /// in the [`Statistics`](crate::aba::statistics::Statistics).
+    fn shortcut(&self, _aba: &PreparedAba) -> Option<(Shortcut, Self::Output)> {
+        None
+    }
+
+    /// The atom this problem decides the acceptance of, if any
+    ///
+    /// Problems returning `Some` are solved on the slice of the framework that is
+    /// relevant for this atom, see [`relevant_slice`]. This is only sound if the
+    /// answer depends on the attackers of the atom alone, which is not the case
+    /// for enumeration, verification or stable semantics.
+    fn slice_query(&self) -> Option<Num> {
+        None
+    }
+
+    /// Which [`Candidate`] literals may be fixed using the grounded extension
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Disabled
+    }
+
+    /// Number of theory copies used by the clauses of this problem, see [`Context`]
+    fn contexts(&self) -> usize {
+        DEFAULT_CONTEXTS
+    }
+
+    /// Assumptions that must keep their identity when breaking symmetries
+    ///
+    /// Problems returning `Some` may be solved with symmetric solutions excluded by
+    /// lex-leader clauses, if enabled by [`Config::symmetries`]. Only symmetries
+    /// fixing the returned assumptions are broken. Enumeration problems must expand
+    /// their solutions with [`Symmetries::expand`] when constructing the output.
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        None
+    }
 }
 
 #[doc(notable_trait)]
 pub trait MultishotProblem {
     type Output;
-    fn additional_clauses(&self, aba: &PreparedAba, iteration: usize) -> ClauseList;
+    fn additional_clauses(
+        &self,
+        aba: &PreparedAba,
+        iteration: usize,
+    ) -> impl Iterator<Item = Clause>;
     fn feedback(&mut self, state: SolverState<'_>, iteration: usize) -> LoopControl;
     fn construct_output(self, state: SolverState<'_>, total_iterations: usize) -> Self::Output;
 
-    fn check(&self, _aba: &Aba) -> Result {
+    fn check(&self, _aba: &PreparedAba) -> Result {
         Ok(())
     }
+
+    /// Which [`Candidate`] literals may be fixed using the grounded extension
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Disabled
+    }
+
+    /// Number of theory copies used by the clauses of this problem, see [`Context`]
+    fn contexts(&self) -> usize {
+        DEFAULT_CONTEXTS
+    }
+
+    /// Assumptions that must keep their identity when breaking symmetries
+    ///
+    /// Problems returning `Some` may be solved with symmetric solutions excluded by
+    /// lex-leader clauses, if enabled by [`Config::symmetries`]. Only symmetries
+    /// fixing the returned assumptions are broken. Enumeration problems must expand
+    /// their solutions with [`Symmetries::expand`] when constructing the output.
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        None
+    }
 }
 
-pub fn solve<P: Problem>(problem: P, aba: Aba, max_loops: Option<usize>) -> Result<P::Output> {
-    let aba = aba.prepare(max_loops);
+pub fn solve<P: Problem>(problem: P, aba: Aba, config: &Config) -> Result<P::Output> {
+    // Only encode the part of the framework that can influence the answer
+    let mut aba = prepare(sliced(aba, problem.slice_query()), config);
     // Let the problem perform additional checks before starting the solver
     problem.check(&aba)?;
+    // Try to answer the problem without the solver
+    if let Some((shortcut, output)) = config.shortcuts.then(|| problem.shortcut(&aba)).flatten() {
+        aba.statistics_mut().shortcut = Some(shortcut);
+        log::info!("Prepared ABA: {}", aba.statistics());
+        if config.proof.is_some() {
+            log::warn!("Answered using {shortcut:?}, no proof is written");
+        }
+        return Ok(output);
+    }
+    log::info!("Prepared ABA: {}", aba.statistics());
+    // Find symmetries of the framework that can be broken for this problem
+    let symmetries = find_symmetries(&aba, config, problem.symmetry_breaking());
     // Create a map that will keep track of the translation between
     // atoms as we know them and their SAT representation
-    let mut map = Mapper::new();
-    // Instantiate a new SAT solver instance
-    let mut sat: Solver = Solver::default();
-    // Derive clauses from the ABA
-    let clauses: ClauseList = aba.derive_clauses::<Candidate>().collect();
-    // Append additional clauses as defined by the problem
-    let additional_clauses = problem.additional_clauses(&aba);
-    // Convert the total of our derived clauses using the mapper
-    // and feed the solver with the result
-    map.as_raw_iter(&clauses)
-        .for_each(|raw| sat.add_clause(raw));
-    // Do the same with the additional clauses that the problem defined
-    map.as_raw_iter(&additional_clauses)
-        .for_each(|raw| sat.add_clause(raw));
+    let map = mapper(&aba, problem.contexts(), &symmetries)?;
+    // Instantiate a new SAT solver instance, trace its proof and apply the limits
+    let mut sat = config.backend.create();
+    if let Some(proof) = &config.proof {
+        sat = Box::new(Traced::new(sat, proof).map_err(Error::Proof)?);
+        write_mapping(proof, &map).map_err(Error::Proof)?;
+    }
+    let watchdog = Watchdog::new(config.limits.solving_deadline(), &config.cancel);
+    watchdog.watch(sat.as_mut(), &config.limits);
+    // Derive clauses from the ABA and add the clauses that the problem defined
+    let clauses = base_clauses(&aba, config, problem.grounded_propagation(), &symmetries)
+        .chain(problem.additional_clauses(&aba));
+    // Convert them using the mapper and feed the solver with the result
+    add_clauses(sat.as_mut(), map.as_raw_iter(clauses), config.clauses);
     // A single solver call to determine the solution
-    if let Some(sat_result) = call_sat_solver(&mut sat) {
+    if let Some(sat_result) = call_sat_solver(sat.as_mut()) {
         #[cfg(debug_assertions)]
         if sat_result {
-            let rec = map.reconstruct(&sat).collect::<Vec<_>>();
+            let rec = map.reconstruct(sat.as_ref()).collect::<Vec<_>>();
             eprintln!("{rec:#?}");
         }
         // If the solver didn't panic, convert our result into the output
@@ -81,48 +262,100 @@ pub fn solve<P: Problem>(problem: P, aba: Aba, max_loops: Option<usize>) -> Resu
         Ok(problem.construct_output(SolverState {
             aba: &aba,
             sat_result,
-            solver: &sat,
+            solver: sat.as_ref(),
             map: &map,
+            symmetries: &symmetries,
         }))
     } else {
-        Err(Error::SatCallInterrupted)
+        Err(watchdog.interruption(sat.as_mut(), &config.limits))
     }
 }
 
 pub fn multishot_solve<P: MultishotProblem>(
-    mut problem: P,
+    problem: P,
     aba: Aba,
-    max_loops: Option<usize>,
+    config: &Config,
 ) -> Result<P::Output> {
-    let aba = aba.prepare(max_loops);
+    multishot_solve_partial(problem, aba, config)?.complete()
+}
+
+/// Like [`multishot_solve`], but keep the solutions found before a solver call
+/// is interrupted, e.g. by a limit of [`Config::limits`]
+pub fn multishot_solve_partial<P: MultishotProblem>(
+    problem: P,
+    aba: Aba,
+    config: &Config,
+) -> Result<Outcome<P::Output>> {
+    if config.proof.is_some() {
+        log::warn!("Proofs are only written for single solver calls, ignoring it");
+    }
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    let watchdog = Watchdog::new(config.limits.solving_deadline(), &config.cancel);
+    multishot_solve_prepared(problem, &aba, config, &watchdog)
+}
+
+/// Like [`multishot_solve_partial`], but on a framework that is already prepared
+/// and with the solver calls interrupted by `watchdog`
+///
+/// Preparing is independent of the problem, many problems can share the result
+/// and the time limit of a single watchdog.
+pub fn multishot_solve_prepared<P: MultishotProblem>(
+    mut problem: P,
+    aba: &PreparedAba,
+    config: &Config,
+    watchdog: &Watchdog,
+) -> Result<Outcome<P::Output>> {
     // Let the problem perform additional checks before starting the solver
-    problem.check(&aba)?;
+    problem.check(aba)?;
+    // Find symmetries of the framework that can be broken for this problem
+    let symmetries = find_symmetries(aba, config, problem.symmetry_breaking());
     // Create a map that will keep track of the translation between
     // atoms as we know them and their SAT representation
-    let mut map = Mapper::new();
-    // Instantiate a new SAT solver instance
-    let mut sat: Solver = Solver::default();
-    // Derive clauses from the ABA
-    let clauses: ClauseList = aba.derive_clauses::<Candidate>().collect();
-    // Convert the total of our derived clauses using the mapper
-    // and feed the solver with the result
-    map.as_raw_iter(&clauses)
-        .for_each(|raw| sat.add_clause(raw));
+    let map = mapper(aba, problem.contexts(), &symmetries)?;
+    // Instantiate a new SAT solver instance and apply the limits
+    let mut sat = config.backend.create();
+    watchdog.watch(sat.as_mut(), &config.limits);
+    // Derive clauses from the ABA, these are added together with the first
+    // additional clauses
+    let mut base = Some(base_clauses(
+        aba,
+        config,
+        problem.grounded_propagation(),
+        &symmetries,
+    ));
     // Keep track of the iteration we're in, this is a multishot solve
     let mut iteration = 0;
     // Enter the main loop
     let final_result = loop {
         // Derive additional clauses from the problem instance, these
-        // may change for every iteration
-        let additional_clauses = problem.additional_clauses(&aba, iteration);
-        // Feed the clauses into our mapper and add the output to our running solver instance
-        map.as_raw_iter(&additional_clauses)
-            .for_each(|raw| sat.add_clause(raw));
-        // Call the solver for the next result
-        let sat_result = call_sat_solver(&mut sat).ok_or(Error::SatCallInterrupted)?;
+        // may change for every iteration. Feed the clauses into our mapper
+        // and add the output to our running solver instance
+        let clauses = base
+            .take()
+            .into_iter()
+            .flatten()
+            .chain(problem.additional_clauses(aba, iteration));
+        add_clauses(sat.as_mut(), map.as_raw_iter(clauses), config.clauses);
+        // Call the solver for the next result, stop early if it is interrupted
+        let Some(sat_result) = call_sat_solver(sat.as_mut()) else {
+            let why = watchdog.interruption(sat.as_mut(), &config.limits);
+            log::warn!("Stopping after {iteration} iterations: {why}");
+            let output = problem.construct_output(
+                SolverState {
+                    aba,
+                    sat_result: false,
+                    solver: sat.as_ref(),
+                    map: &map,
+                    symmetries: &symmetries,
+                },
+                iteration,
+            );
+            return Ok(Outcome::Partial(output, why));
+        };
         #[cfg(debug_assertions)]
         if sat_result {
-            let rec = map.reconstruct(&sat).collect::<Vec<_>>();
+            let rec = map.reconstruct(sat.as_ref()).collect::<Vec<_>>();
             eprintln!("{rec:#?}");
         }
         // Call our problem to ask whether we should continue. This is the point
@@ -130,10 +363,11 @@ pub fn multishot_solve<P: MultishotProblem>(
         // with the solver feedback and continue
         let control = problem.feedback(
             SolverState {
-                aba: &aba,
+                aba,
                 sat_result,
-                solver: &sat,
+                solver: sat.as_ref(),
                 map: &map,
+                symmetries: &symmetries,
             },
             iteration,
         );
@@ -147,21 +381,111 @@ pub fn multishot_solve<P: MultishotProblem>(
     // This point will only be reached if the problem instance
     // is happy with the iterations. Call it one final time to
     // construct the output using the final results
-    Ok(problem.construct_output(
+    Ok(Outcome::Complete(problem.construct_output(
         SolverState {
-            aba: &aba,
+            aba,
             sat_result: final_result,
-            solver: &sat,
+            solver: sat.as_ref(),
             map: &map,
+            symmetries: &symmetries,
         },
         iteration,
-    ))
+    )))
+}
+
+/// Prepare `aba` for solving, searching loops within the limits of `config`
+fn prepare(aba: Aba, config: &Config) -> PreparedAba {
+    aba.prepare_until(
+        config.max_loops,
+        config.limits.loop_deadline(),
+        &CancellationToken::linked([&config.cancel, &config.stop_loops]),
+        config.simplify,
+    )
+}
+
+/// The part of `aba` relevant for `query`, the whole framework without a query
+fn sliced(aba: Aba, query: Option<Num>) -> Aba {
+    match query {
+        Some(query) => relevant_slice(&aba, query).unwrap_or(aba),
+        None => aba,
+    }
+}
+
+/// Variables for `contexts` copies of the theory and the helpers of `symmetries`
+fn mapper(aba: &PreparedAba, contexts: usize, symmetries: &Symmetries) -> Result<Mapper> {
+    Mapper::new(aba)
+        .with_contexts(contexts)
+        .with_symmetry_helpers(symmetries.helper_count())
+        .checked()
+}
+
+/// Clauses of the first solver call of every problem, before its additional clauses
+///
+/// The derivations of the [`Context::CANDIDATE`], the literals the grounded
+/// extension already determines and the lex-leader clauses of the `symmetries`.
+fn base_clauses<'a>(
+    aba: &'a PreparedAba,
+    config: &Config,
+    propagation: Propagation,
+    symmetries: &'a Symmetries,
+) -> impl Iterator<Item = Clause> + 'a {
+    aba.derive_clauses(Context::CANDIDATE)
+        .chain(grounded_clauses(aba, config, propagation))
+        .chain(symmetries.lex_leader_clauses())
+}
+
+/// Unit clauses for the assumptions determined by the grounded extension
+fn grounded_clauses(aba: &PreparedAba, config: &Config, propagation: Propagation) -> Vec<Clause> {
+    if !config.grounded || propagation == Propagation::Disabled {
+        return vec![];
+    }
+    let grounded = Grounded::of(aba, aba.index());
+    let defeated = grounded
+        .defeated
+        .iter()
+        .map(|assumption| Clause::from(vec![Candidate::from(*assumption).neg()]));
+    let accepted = grounded
+        .accepted
+        .iter()
+        .filter(|_| propagation == Propagation::Complete)
+        .map(|assumption| Clause::from(vec![Candidate::from(*assumption).pos()]));
+    defeated.chain(accepted).collect()
+}
+
+/// Symmetries of the framework fixing the given assumptions, if enabled
+fn find_symmetries(aba: &PreparedAba, config: &Config, fixed: Option<Vec<Num>>) -> Symmetries {
+    match fixed {
+        Some(fixed) if config.symmetries => Symmetries::of(aba, &fixed),
+        _ => Symmetries::default(),
+    }
+}
+
+/// Hand a batch of clauses to the solver, simplifying them first
+fn add_clauses<I: Iterator<Item = RawClause>>(
+    sat: &mut dyn Backend,
+    clauses: I,
+    simplification: ClauseSimplification,
+) {
+    simplified(clauses, simplification).for_each(|raw| sat.add_clause(raw));
+}
+
+/// Simplify a batch of clauses, they are streamed if simplification is off
+fn simplified<'a, I: Iterator<Item = RawClause> + 'a>(
+    clauses: I,
+    simplification: ClauseSimplification,
+) -> Box<dyn Iterator<Item = RawClause> + 'a> {
+    if simplification == ClauseSimplification::Off {
+        return Box::new(clauses);
+    }
+    let (clauses, statistics) = simplify_clauses(clauses, simplification);
+    log::info!("Simplified clauses: {statistics}");
+    Box::new(clauses.into_iter())
 }
 
 #[cfg_attr(
     feature = "timing",
     fun_time::fun_time(message = "Calling SAT solver", reporting = "log")
 )]
-fn call_sat_solver(sat: &mut Solver) -> Option<bool> {
+fn call_sat_solver(sat: &mut dyn Backend) -> Option<bool> {
     sat.solve()
 }
@@ -0,0 +1,148 @@
+//! Enumerate complete extensions on multiple threads
+//!
+//! The search space is split into cubes. A cube fixes every assumption of a small
+//! set to be either in or out of the extension. The cubes are disjoint and cover all
+//! extensions, so every cube can be enumerated on its own and the results are merged.
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{
+    aba::{Aba, Num},
+    cancel::CancellationToken,
+    error::Error,
+    Result,
+};
+
+use super::{
+    complete::EnumerateCompleteExtensions, limits::Watchdog, multishot_solve_prepared, prepare,
+    split::EnumerateConditioned, Config, Outcome,
+};
+
+/// Number of cubes per thread, more cubes balance the work better
+const CUBES_PER_THREAD: usize = 4;
+
+/// Enumerate all complete extensions using `threads` worker threads
+///
+/// The framework is prepared once and shared, the workers only create a solver per
+/// cube. The extensions are sorted by their sorted list of assumptions, the output
+/// does not depend on the number of threads or the order the cubes are finished in.
+///
+/// The time limit for solving of [`Config::limits`] applies to all cubes together.
+/// Once a cube is interrupted the other workers stop as well, the outcome is then
+/// partial and contains the extensions of all cubes found until then.
+pub fn enumerate_complete(
+    aba: Aba,
+    config: &Config,
+    threads: usize,
+) -> Result<Outcome<Vec<HashSet<Num>>>> {
+    if config.proof.is_some() {
+        log::warn!("Proofs are only written for single solver calls, ignoring it");
+    }
+    let threads = threads.max(1);
+    let cube_assumptions = cube_assumptions(&aba, threads * CUBES_PER_THREAD);
+    let cubes: Vec<Vec<(Num, bool)>> = (0..1usize << cube_assumptions.len())
+        .map(|bits| {
+            cube_assumptions
+                .iter()
+                .enumerate()
+                .map(|(idx, assumption)| (*assumption, bits & (1 << idx) != 0))
+                .collect()
+        })
+        .collect();
+    log::info!(
+        "Enumerating {} cubes over {:?} on {threads} threads",
+        cubes.len(),
+        cube_assumptions
+    );
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    // Cancelled by the first cube that is interrupted
+    let stop = CancellationToken::linked([&config.cancel]);
+    let watchdog = Watchdog::new(config.limits.solving_deadline(), &stop);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..cubes.len()).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..threads.min(cubes.len()) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(cube) = cubes.get(idx).filter(|_| !stop.is_cancelled()) else {
+                    break;
+                };
+                let problem = EnumerateConditioned {
+                    fixed: cube.clone(),
+                    inner: EnumerateCompleteExtensions::default(),
+                };
+                let result = multishot_solve_prepared(problem, &aba, config, &watchdog);
+                if !matches!(result, Ok(Outcome::Complete(_))) {
+                    stop.cancel();
+                }
+                results.lock().expect("no worker panicked")[idx] = Some(result);
+            });
+        }
+    });
+    let mut extensions = vec![];
+    let mut interruptions = vec![];
+    // Cubes not started after an interruption are missing
+    for result in results
+        .into_inner()
+        .expect("no worker panicked")
+        .into_iter()
+        .flatten()
+    {
+        match result? {
+            Outcome::Complete(found) => extensions.extend(found),
+            Outcome::Partial(found, why) => {
+                extensions.extend(found);
+                interruptions.push(why);
+            }
+        }
+    }
+    extensions.sort_by_cached_key(|extension| {
+        let mut sorted: Vec<_> = extension.iter().cloned().collect();
+        sorted.sort_unstable();
+        sorted
+    });
+    // The other cubes were cancelled because of the first interruption
+    let why = match interruptions
+        .iter()
+        .position(|why| !matches!(why, Error::Cancelled))
+    {
+        Some(idx) => Some(interruptions.swap_remove(idx)),
+        None => interruptions.pop(),
+    };
+    Ok(match why {
+        Some(why) => Outcome::Partial(extensions, why),
+        None => Outcome::Complete(extensions),
+    })
+}
+
+/// Assumptions to build the cubes from, enough for at least `cubes` cubes
+///
+/// Assumptions used in the most rule bodies are chosen first, fixing them
+/// simplifies the largest part of the framework.
+fn cube_assumptions(aba: &Aba, cubes: usize) -> Vec<Num> {
+    let count = cubes.next_power_of_two().trailing_zeros() as usize;
+    let mut assumptions: Vec<_> = aba
+        .assumptions()
+        .map(|assumption| {
+            let uses = aba
+                .rules
+                .iter()
+                .filter(|(_, body)| body.contains(assumption))
+                .count();
+            (uses, *assumption)
+        })
+        .collect();
+    // Most uses first, ties are broken by the assumption to stay deterministic
+    assumptions.sort_unstable_by(|left, right| right.0.cmp(&left.0).then(left.1.cmp(&right.1)));
+    assumptions
+        .into_iter()
+        .take(count)
+        .map(|(_, assumption)| assumption)
+        .collect()
+}
@@ -0,0 +1,204 @@
+//! Compare the acceptance of all assumptions across semantics
+//!
+//! The framework is prepared once and the acceptance under every [`Semantics`] is
+//! computed on it. Conflict-freeness and the grounded extension need no solver, the
+//! other semantics use [`acceptance`](super::acceptance).
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{
+    aba::{
+        grounded::{derivable_from, Grounded},
+        prepared::PreparedAba,
+        Aba, Num,
+    },
+    Result,
+};
+
+use super::{
+    acceptance::{accept_complete, accept_preferred, accept_stable, Acceptance},
+    prepare, Config,
+};
+
+/// Semantics compared in a [`Report`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Semantics {
+    ConflictFree,
+    Admissible,
+    Complete,
+    Grounded,
+    Stable,
+    Preferred,
+}
+
+impl Semantics {
+    pub const ALL: [Semantics; 6] = [
+        Semantics::ConflictFree,
+        Semantics::Admissible,
+        Semantics::Complete,
+        Semantics::Grounded,
+        Semantics::Stable,
+        Semantics::Preferred,
+    ];
+
+    /// Abbreviation used by ICCMA
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Semantics::ConflictFree => "cf",
+            Semantics::Admissible => "ad",
+            Semantics::Complete => "co",
+            Semantics::Grounded => "gr",
+            Semantics::Stable => "st",
+            Semantics::Preferred => "pr",
+        }
+    }
+}
+
+/// Acceptance of every assumption under all [`Semantics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// All assumptions of the framework, sorted
+    pub assumptions: Vec<Num>,
+    /// One entry per semantics, in the order of [`Semantics::ALL`]
+    pub semantics: Vec<(Semantics, Acceptance)>,
+}
+
+pub fn report(aba: Aba, config: &Config) -> Result<Report> {
+    let aba = prepare(aba, config);
+    log::info!("Prepared ABA: {}", aba.statistics());
+    let mut assumptions: Vec<_> = aba.assumptions().cloned().collect();
+    assumptions.sort_unstable();
+    let complete = accept_complete(&aba, config)?;
+    let semantics = Semantics::ALL
+        .into_iter()
+        .map(|semantics| {
+            let acceptance = match semantics {
+                Semantics::ConflictFree => conflict_free_acceptance(&aba),
+                // The empty set is admissible, nothing is accepted skeptically. Every
+                // admissible extension is part of a complete one.
+                Semantics::Admissible => Acceptance {
+                    exists: true,
+                    credulous: complete.credulous.clone(),
+                    skeptical: HashSet::new(),
+                    witnesses: complete.witnesses.clone(),
+                },
+                Semantics::Complete => complete.clone(),
+                Semantics::Grounded => {
+                    let grounded = Grounded::of(&aba, aba.index());
+                    Acceptance {
+                        exists: true,
+                        credulous: grounded.accepted.clone(),
+                        skeptical: grounded.accepted.clone(),
+                        witnesses: vec![grounded.accepted],
+                    }
+                }
+                Semantics::Stable => accept_stable(&aba, config)?,
+                Semantics::Preferred => accept_preferred(&aba, config)?,
+            };
+            Ok((semantics, acceptance))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Report {
+        assumptions,
+        semantics,
+    })
+}
+
+/// An assumption is in a conflict-free set if it does not attack itself
+fn conflict_free_acceptance(aba: &PreparedAba) -> Acceptance {
+    let credulous = aba
+        .inverses
+        .iter()
+        .filter(|(assumption, inverse)| {
+            !derivable_from(aba, aba.index(), [*assumption]).contains(inverse)
+        })
+        .map(|(assumption, _)| *assumption)
+        .collect();
+    // The empty set is conflict-free
+    Acceptance {
+        exists: true,
+        credulous,
+        skeptical: HashSet::new(),
+        witnesses: vec![],
+    }
+}
+
+impl Report {
+    /// A table with one row per assumption and one column per semantics
+    ///
+    /// The first row states whether the semantics has an extension. Every cell
+    /// of the assumptions shows `c` if it is accepted credulously and `s` if it is
+    /// accepted skeptically, `-` otherwise.
+    pub fn to_table(&self) -> Result<String> {
+        let width = self
+            .assumptions
+            .iter()
+            .map(|assumption| assumption.to_string().len())
+            .chain([6])
+            .max()
+            .unwrap_or_default();
+        let mut table = format!("{:width$}", "");
+        for (semantics, _) in &self.semantics {
+            write!(table, " {:>3}", semantics.short_name())?;
+        }
+        write!(table, "\n{:width$}", "exists")?;
+        for (_, acceptance) in &self.semantics {
+            write!(
+                table,
+                " {:>3}",
+                if acceptance.exists { "yes" } else { "no" }
+            )?;
+        }
+        for assumption in &self.assumptions {
+            write!(table, "\n{assumption:<width$}")?;
+            for (_, acceptance) in &self.semantics {
+                let credulous = if acceptance.credulous.contains(assumption) {
+                    'c'
+                } else {
+                    '-'
+                };
+                let skeptical = if acceptance.skeptical.contains(assumption) {
+                    's'
+                } else {
+                    '-'
+                };
+                write!(table, "  {credulous}{skeptical}")?;
+            }
+        }
+        Ok(table)
+    }
+
+    /// A JSON object with the sorted assumptions and the acceptance per semantics
+    pub fn to_json(&self) -> Result<String> {
+        let list = |set: &HashSet<Num>| {
+            let mut list: Vec<_> = set.iter().cloned().collect();
+            list.sort_unstable();
+            list.iter()
+                .map(Num::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut json = format!(
+            "{{\"assumptions\":[{}],\"semantics\":{{",
+            self.assumptions
+                .iter()
+                .map(Num::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        for (idx, (semantics, acceptance)) in self.semantics.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "\"{}\":{{\"exists\":{},\"credulous\":[{}],\"skeptical\":[{}]}}",
+                semantics.short_name(),
+                acceptance.exists,
+                list(&acceptance.credulous),
+                list(&acceptance.skeptical)
+            )?;
+        }
+        json.push_str("}}");
+        Ok(json)
+    }
+}
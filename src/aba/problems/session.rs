@@ -0,0 +1,155 @@
+//! Answer many queries on the same framework with a single solver
+//!
+//! [`solve`](super::solve) prepares the framework and creates a new solver for every
+//! problem. A [`Session`] does this once and keeps the solver with the admissibility
+//! encoding loaded. Queries only add assumptions to the solver calls, the clauses
+//! stay valid for all following queries and the solver keeps what it learned.
+use std::collections::HashSet;
+
+use crate::{
+    aba::{prepared::PreparedAba, Aba, Context, Num},
+    error::{Error, Result},
+    literal::{lits::Candidate, IntoLiteral, Literal},
+    mapper::Mapper,
+    sat::Backend,
+};
+
+use super::{
+    add_clauses,
+    admissibility::{credulous_shortcut, initial_admissibility_clauses},
+    grounded_clauses,
+    limits::{Limits, Watchdog},
+    prepare, Config, Propagation,
+};
+
+/// A prepared framework together with a solver holding its encoding
+///
+/// Symmetry breaking and slicing are never applied, both depend on the query.
+/// The time limit for solving of [`Config::limits`] applies to the whole session.
+pub struct Session {
+    aba: PreparedAba,
+    shortcuts: bool,
+    map: Mapper,
+    sat: Box<dyn Backend>,
+    watchdog: Watchdog,
+    limits: Limits,
+}
+
+impl Session {
+    pub fn new(aba: Aba, config: &Config) -> Result<Self> {
+        let aba = prepare(aba, config);
+        log::info!("Prepared ABA: {}", aba.statistics());
+        let map = Mapper::new(&aba).checked()?;
+        let mut sat = config.backend.create();
+        let watchdog = Watchdog::new(config.limits.solving_deadline(), &config.cancel);
+        watchdog.watch(sat.as_mut(), &config.limits);
+        // Assumptions defeated by the grounded extension are in no admissible extension
+        let clauses = aba
+            .derive_clauses(Context::CANDIDATE)
+            .chain(grounded_clauses(&aba, config, Propagation::Defeated))
+            .chain(initial_admissibility_clauses(&aba));
+        add_clauses(sat.as_mut(), map.as_raw_iter(clauses), config.clauses);
+        Ok(Session {
+            aba,
+            shortcuts: config.shortcuts,
+            map,
+            sat,
+            watchdog,
+            limits: config.limits.clone(),
+        })
+    }
+
+    /// The framework all queries are answered on
+    pub fn aba(&self) -> &PreparedAba {
+        &self.aba
+    }
+
+    /// Decide whether `assumption` is in some admissible extension
+    pub fn decide_credulous_admissible(&mut self, assumption: Num) -> Result<bool> {
+        if !self.aba.contains_assumption(&assumption) {
+            return Err(Error::ProblemCheckFailed(format!(
+                "Assumption {assumption:?} not present in ABA framework"
+            )));
+        }
+        self.credulous(assumption)
+    }
+
+    /// Decide whether `element` is derivable from some complete extension
+    ///
+    /// Every admissible extension is contained in a complete one, the admissibility
+    /// encoding answers this query as well.
+    pub fn decide_credulous_complete(&mut self, element: Num) -> Result<bool> {
+        if !self.aba.contains_atom(&element) {
+            return Err(Error::ProblemCheckFailed(format!(
+                "Element {element:?} not present in ABA framework"
+            )));
+        }
+        self.credulous(element)
+    }
+
+    /// Verify whether `assumptions` is an admissible extension
+    pub fn verify_admissible(&mut self, assumptions: &HashSet<Num>) -> Result<bool> {
+        if let Some(assumption) = assumptions
+            .iter()
+            .find(|assumption| !self.aba.contains_assumption(assumption))
+        {
+            return Err(Error::ProblemCheckFailed(format!(
+                "Assumption {assumption:?} not present in ABA framework"
+            )));
+        }
+        let members = self
+            .aba
+            .assumptions()
+            .map(|assumption| {
+                let inf = Candidate::from(*assumption);
+                if assumptions.contains(assumption) {
+                    inf.pos()
+                } else {
+                    inf.neg()
+                }
+            })
+            .collect::<Vec<_>>();
+        self.solve_with(&members)
+    }
+
+    /// Assumptions in the extension found by the last satisfiable solver call
+    ///
+    /// Queries answered by a shortcut do not call the solver and leave this unchanged.
+    pub fn last_extension(&self) -> HashSet<Num> {
+        self.aba
+            .assumptions()
+            .filter(|assumption| {
+                let literal = Candidate::from(**assumption).pos();
+                self.map
+                    .get_raw(&literal)
+                    .is_some_and(|raw| self.sat.value(raw) == Some(true))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn credulous(&mut self, element: Num) -> Result<bool> {
+        if self.shortcuts {
+            if let Some((shortcut, result)) = credulous_shortcut(&self.aba, element) {
+                log::debug!("Answered {element} using {shortcut:?}");
+                return Ok(result);
+            }
+        }
+        // An element without representative can never be derived
+        match self.aba.representative(element) {
+            Some(element) => self.solve_with(&[Candidate::from(element).pos()]),
+            None => Ok(false),
+        }
+    }
+
+    /// A single solver call with the literals assumed to be true
+    fn solve_with(&mut self, assumptions: &[Literal]) -> Result<bool> {
+        let assumptions = assumptions
+            .iter()
+            .map(|literal| self.map.as_raw(literal))
+            .collect::<Vec<_>>();
+        self.sat
+            .solve_with(&assumptions)
+            .ok_or_else(|| self.watchdog.interruption(self.sat.as_mut(), &self.limits))
+    }
+}
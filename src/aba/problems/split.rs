@@ -0,0 +1,202 @@
+//! Enumerate and count complete extensions by splitting the framework
+//!
+//! An assumption `a` depends on an assumption `b`, if `b` can be used to derive the
+//! contrary of `a`. The strongly connected components of this dependency graph are
+//! solved one after another, attackers first. The part of a complete extension inside
+//! a component only depends on its ancestors, so each component is solved once for
+//! every distinct choice of its ancestors and the results are combined.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{
+    aba::{
+        index::AbaIndex, prepared::PreparedAba, slice::relevant_slice_of,
+        traverse::strongly_connected_components, Aba, Num,
+    },
+    clauses::Clause,
+    literal::{lits::Candidate, IntoLiteral},
+    Result,
+};
+
+use super::{
+    complete::EnumerateCompleteExtensions, multishot_solve, Config, LoopControl, MultishotProblem,
+    Propagation, SolverState,
+};
+
+/// Enumerate all complete extensions, solving every component on its own
+pub fn enumerate_complete(aba: Aba, config: &Config) -> Result<Vec<HashSet<Num>>> {
+    let components = components(&aba);
+    log::info!("Split ABA into {} components", components.len());
+    enumerate_components(&aba, &components, config)
+}
+
+/// Count the complete extensions
+///
+/// Components that are not connected in any way are enumerated independently
+/// and only their counts are multiplied.
+pub fn count_complete(aba: Aba, config: &Config) -> Result<u128> {
+    let groups = independent_groups(&aba, components(&aba));
+    log::info!("Split ABA into {} independent groups", groups.len());
+    groups.iter().try_fold(1, |count, group| {
+        let extensions = enumerate_components(&aba, group, config)?;
+        Ok(count * extensions.len() as u128)
+    })
+}
+
+/// Enumerate the complete extensions of `aba` restricted to the assumptions of `components`
+///
+/// The `components` must be ordered attackers first and contain all their ancestors.
+fn enumerate_components(
+    aba: &Aba,
+    components: &[Vec<Num>],
+    config: &Config,
+) -> Result<Vec<HashSet<Num>>> {
+    let mut partial = vec![HashSet::new()];
+    for component in components {
+        let members: HashSet<_> = component.iter().cloned().collect();
+        let slice = relevant_slice_of(aba, component.iter().cloned());
+        let mut ancestors: Vec<_> = slice
+            .assumptions()
+            .filter(|assumption| !members.contains(assumption))
+            .cloned()
+            .collect();
+        ancestors.sort_unstable();
+        // Results of the component, keyed by the accepted ancestors
+        let mut solved: HashMap<Vec<Num>, Vec<HashSet<Num>>> = HashMap::new();
+        let mut next = vec![];
+        for set in partial {
+            let key: Vec<_> = ancestors
+                .iter()
+                .filter(|ancestor| set.contains(*ancestor))
+                .cloned()
+                .collect();
+            if !solved.contains_key(&key) {
+                let problem = EnumerateConditioned {
+                    fixed: ancestors
+                        .iter()
+                        .map(|ancestor| (*ancestor, set.contains(ancestor)))
+                        .collect(),
+                    inner: EnumerateCompleteExtensions::default(),
+                };
+                let parts = multishot_solve(problem, slice.clone(), config)?
+                    .into_iter()
+                    .map(|extension| {
+                        extension
+                            .into_iter()
+                            .filter(|assumption| members.contains(assumption))
+                            .collect()
+                    })
+                    .collect();
+                solved.insert(key.clone(), parts);
+            }
+            next.extend(
+                solved[&key]
+                    .iter()
+                    .map(|part| set.union(part).cloned().collect()),
+            );
+        }
+        partial = next;
+    }
+    Ok(partial)
+}
+
+/// Strongly connected components of the dependency graph, attackers first
+///
+/// Only components containing assumptions are returned.
+fn components(aba: &Aba) -> Vec<Vec<Num>> {
+    // An edge points from an atom to the atoms it can be derived from,
+    // and from an assumption to its contrary
+    let mut succ = BTreeMap::<Num, BTreeSet<Num>>::new();
+    for (head, body) in &aba.rules {
+        succ.entry(*head).or_default().extend(body);
+    }
+    for (assumption, inverse) in &aba.inverses {
+        succ.entry(*assumption).or_default().insert(*inverse);
+    }
+    strongly_connected_components(&succ)
+        .into_iter()
+        .map(|scc| {
+            scc.into_iter()
+                .filter(|atom| aba.contains_assumption(atom))
+                .collect::<Vec<_>>()
+        })
+        .filter(|component| !component.is_empty())
+        .collect()
+}
+
+/// Group the `components` by the weakly connected part of the framework they belong to
+///
+/// The order of the components is kept within every group.
+fn independent_groups(aba: &Aba, components: Vec<Vec<Num>>) -> Vec<Vec<Vec<Num>>> {
+    let index = AbaIndex::new(aba);
+    let mut parent: Vec<usize> = (0..index.len()).collect();
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+    let mut union = |left: &Num, right: &Num| {
+        let left = find(&mut parent, index.position(left).unwrap());
+        let right = find(&mut parent, index.position(right).unwrap());
+        parent[left] = right;
+    };
+    for (head, body) in &aba.rules {
+        body.iter().for_each(|atom| union(head, atom));
+    }
+    for (assumption, inverse) in &aba.inverses {
+        union(assumption, inverse);
+    }
+    let mut groups: Vec<Vec<Vec<Num>>> = vec![];
+    let mut group_of_root = HashMap::new();
+    for component in components {
+        let root = find(&mut parent, index.position(&component[0]).unwrap());
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[group].push(component);
+    }
+    groups
+}
+
+/// Enumerate complete extensions with some assumptions fixed to a value
+pub(super) struct EnumerateConditioned {
+    pub(super) fixed: Vec<(Num, bool)>,
+    pub(super) inner: EnumerateCompleteExtensions,
+}
+
+impl MultishotProblem for EnumerateConditioned {
+    type Output = Vec<HashSet<Num>>;
+
+    fn additional_clauses(
+        &self,
+        aba: &PreparedAba,
+        iteration: usize,
+    ) -> impl Iterator<Item = Clause> {
+        let fixed = match iteration {
+            0 => self.fixed.as_slice(),
+            _ => &[],
+        };
+        let fixed = fixed.iter().map(|(assumption, accepted)| {
+            let literal = Candidate::from(*assumption);
+            match accepted {
+                true => Clause::from(vec![literal.pos()]),
+                false => Clause::from(vec![literal.neg()]),
+            }
+        });
+        self.inner.additional_clauses(aba, iteration).chain(fixed)
+    }
+
+    fn feedback(&mut self, state: SolverState<'_>, iteration: usize) -> LoopControl {
+        self.inner.feedback(state, iteration)
+    }
+
+    fn construct_output(self, state: SolverState<'_>, total_iterations: usize) -> Self::Output {
+        self.inner.construct_output(state, total_iterations)
+    }
+
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Complete
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use crate::{
+    aba::{prepared::PreparedAba, Num},
+    clauses::Clause,
+    literal::{lits::Candidate, IntoLiteral},
+};
+
+use super::{
+    admissibility::blocking_clause, LoopControl, MultishotProblem, Propagation, SolverState,
+};
+
+/// Compute all stable extensions for an [`Aba`](crate::aba::Aba)
+#[derive(Debug, Default)]
+pub struct EnumerateStableExtensions {
+    found: Vec<HashSet<Num>>,
+}
+
+/// Clauses restricting the candidate to the stable extensions
+///
+/// Only the [`Context::CANDIDATE`](crate::aba::Context::CANDIDATE) is used.
+pub fn initial_stable_clauses(aba: &PreparedAba) -> impl Iterator<Item = Clause> + '_ {
+    aba.inverses.iter().flat_map(|(assumption, inverse)| {
+        [
+            // Ensure conflict-freeness
+            // For any assumption `a` and it's inverse `b`:
+            //   a in th(Candidate) and b in th(Candidate) => bottom
+            Clause::from(vec![
+                Candidate::from(*assumption).neg(),
+                Candidate::from(*inverse).neg(),
+            ]),
+            // Attack everything outside of the set
+            // For any assumption `a` and it's inverse `b`:
+            //   a not in th(Candidate) => b in th(Candidate)
+            Clause::from(vec![
+                Candidate::from(*assumption).pos(),
+                Candidate::from(*inverse).pos(),
+            ]),
+        ]
+    })
+}
+
+impl MultishotProblem for EnumerateStableExtensions {
+    type Output = Vec<HashSet<Num>>;
+
+    fn additional_clauses(
+        &self,
+        aba: &PreparedAba,
+        iteration: usize,
+    ) -> impl Iterator<Item = Clause> {
+        let initial = match iteration {
+            0 => Some(initial_stable_clauses(aba)),
+            _ => None,
+        };
+        let blocking = match iteration {
+            0 => None,
+            idx => Some(blocking_clause(aba, &self.found[idx - 1])),
+        };
+        initial.into_iter().flatten().chain(blocking)
+    }
+
+    fn feedback(&mut self, state: SolverState<'_>, _iteration: usize) -> LoopControl {
+        if !state.sat_result {
+            return LoopControl::Stop;
+        }
+        let found = state
+            .aba
+            .inverses
+            .keys()
+            .filter_map(|assumption| {
+                let literal = Candidate::from(*assumption).pos();
+                let raw = state.map.get_raw(&literal)?;
+                match state.solver.value(raw) {
+                    Some(true) => Some(*assumption),
+                    _ => None,
+                }
+            })
+            .collect();
+        self.found.push(found);
+        LoopControl::Continue
+    }
+
+    fn construct_output(self, state: SolverState<'_>, _total_iterations: usize) -> Self::Output {
+        // Add the sets excluded by symmetry breaking
+        state.symmetries.expand(self.found)
+    }
+
+    /// Stable extensions are complete
+    fn grounded_propagation(&self) -> Propagation {
+        Propagation::Complete
+    }
+
+    fn contexts(&self) -> usize {
+        1
+    }
+
+    fn symmetry_breaking(&self) -> Option<Vec<Num>> {
+        Some(vec![])
+    }
+}
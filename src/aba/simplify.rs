@@ -0,0 +1,274 @@
+//! Simplification of an [`Aba`] before it is translated to SAT
+//!
+//! All steps preserve the semantics of the framework for every assumption
+//! and every atom that is not removed. Removed atoms are recorded together
+//! with the atom that replaces them.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use bit_set::BitSet;
+
+use super::{index::AbaIndex, Aba, Num};
+
+/// Report of the changes done by [`simplify`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Simplification {
+    /// Atoms that are derivable without any assumption
+    pub facts: usize,
+    /// Assumptions whose contrary is a fact, these are never part of an extension
+    pub out_assumptions: usize,
+    /// Rules removed, because their head is a fact
+    pub fact_rules: usize,
+    /// Rules removed, because their body contains an out assumption
+    pub attacked_rules: usize,
+    /// Rules removed, because another rule with the same head and body exists
+    pub duplicate_rules: usize,
+    /// Rules removed, because another rule with the same head and a smaller body exists
+    pub subsumed_rules: usize,
+    /// Atoms replaced by an equivalent atom
+    pub merged_atoms: usize,
+}
+
+/// Simplify the `aba` in place
+///
+/// 1. Facts are propagated. Rules for facts are replaced by a single rule without
+///    a body and facts are removed from all bodies.
+/// 2. Assumptions whose contrary is a fact can never be part of a conflict-free set,
+///    nor be used by an attacker. All rules using them are dropped.
+/// 3. Atoms `p` that are not assumptions and whose only rule is `p <- q` are replaced by `q`.
+/// 4. Rules whose body is a superset of another rule with the same head are dropped.
+///
+/// Returns the report and the replacement for every merged atom.
+#[cfg_attr(
+    feature = "timing",
+    fun_time::fun_time(message = "Simplifying ABA", reporting = "log")
+)]
+pub fn simplify(aba: &mut Aba) -> (Simplification, HashMap<Num, Num>) {
+    let mut report = Simplification::default();
+    propagate_facts(aba, &mut report);
+    let representatives = merge_equivalent_atoms(aba, &mut report);
+    remove_subsumed_rules(aba, &mut report);
+    log::info!("Simplified ABA: {report}");
+    (report, representatives)
+}
+
+/// All atoms that are derivable from the empty set of assumptions
+fn facts(aba: &Aba) -> HashSet<Num> {
+    let index = AbaIndex::new(aba);
+    let mut facts = HashSet::new();
+    // Number of body atoms per rule that are not yet known to be facts
+    let mut missing: Vec<_> = aba.rules.iter().map(|(_, body)| body.len()).collect();
+    let mut queue: Vec<Num> = aba
+        .rules
+        .iter()
+        .filter(|(_, body)| body.is_empty())
+        .map(|(head, _)| *head)
+        .collect();
+    while let Some(atom) = queue.pop() {
+        // Only flat ABA is supported, assumptions are never derived
+        if index.is_assumption(&atom) || !facts.insert(atom) {
+            continue;
+        }
+        for rule_id in index.rules_with_body(&atom) {
+            missing[*rule_id] -= 1;
+            if missing[*rule_id] == 0 {
+                queue.push(aba.rules[*rule_id].0);
+            }
+        }
+    }
+    facts
+}
+
+fn propagate_facts(aba: &mut Aba, report: &mut Simplification) {
+    let facts = facts(aba);
+    let out: HashSet<_> = aba
+        .inverses
+        .iter()
+        .filter(|(_, inverse)| facts.contains(inverse))
+        .map(|(assumption, _)| *assumption)
+        .collect();
+    report.facts = facts.len();
+    report.out_assumptions = out.len();
+    let mut fact_heads = HashSet::new();
+    aba.rules.retain_mut(|(head, body)| {
+        if facts.contains(head) {
+            // Keep a single rule without a body for every fact
+            if fact_heads.insert(*head) {
+                body.clear();
+                true
+            } else {
+                report.fact_rules += 1;
+                false
+            }
+        } else if body.iter().any(|atom| out.contains(atom)) {
+            report.attacked_rules += 1;
+            false
+        } else {
+            body.retain(|atom| !facts.contains(atom));
+            true
+        }
+    });
+}
+
+fn merge_equivalent_atoms(aba: &mut Aba, report: &mut Simplification) -> HashMap<Num, Num> {
+    let index = AbaIndex::new(aba);
+    // Atoms that are defined by a single rule with a single atom in the body
+    let candidates: HashMap<Num, Num> = index
+        .atoms()
+        .iter()
+        .filter(|atom| !index.is_assumption(atom))
+        .filter_map(|atom| match index.rules_with_head(atom) {
+            [rule_id] if aba.rules[*rule_id].1.len() == 1 => {
+                aba.rules[*rule_id].1.first().map(|other| (*atom, *other))
+            }
+            _ => None,
+        })
+        .collect();
+    // Follow the chains of candidates to their end, atoms on a cycle keep their rules.
+    // `None` marks atoms whose chain ends in a cycle
+    let mut resolved: HashMap<Num, Option<Num>> = HashMap::new();
+    for atom in candidates.keys() {
+        let mut path = vec![];
+        let mut on_path = HashSet::new();
+        let mut current = *atom;
+        let representative = loop {
+            if let Some(representative) = resolved.get(&current) {
+                break *representative;
+            }
+            match candidates.get(&current) {
+                Some(_) if !on_path.insert(current) => break None,
+                Some(next) => {
+                    path.push(current);
+                    current = *next;
+                }
+                None => break Some(current),
+            }
+        };
+        for atom in path {
+            resolved.insert(atom, representative);
+        }
+    }
+    let representatives: HashMap<Num, Num> = resolved
+        .into_iter()
+        .filter_map(|(atom, representative)| Some((atom, representative?)))
+        .collect();
+    report.merged_atoms = representatives.len();
+    if representatives.is_empty() {
+        return representatives;
+    }
+    let replace = |atom: &Num| *representatives.get(atom).unwrap_or(atom);
+    aba.rules.retain_mut(|(head, body)| {
+        if representatives.contains_key(head) {
+            return false;
+        }
+        *body = body.iter().map(replace).collect();
+        // Merging may introduce trivial cycles
+        !body.contains(head)
+    });
+    aba.inverses
+        .values_mut()
+        .for_each(|inverse| *inverse = replace(inverse));
+    representatives
+}
+
+fn remove_subsumed_rules(aba: &mut Aba, report: &mut Simplification) {
+    let index = AbaIndex::new(aba);
+    let mut removed = BitSet::new();
+    for head in index.atoms() {
+        let mut rule_ids = index.rules_with_head(head).to_vec();
+        if rule_ids.len() < 2 {
+            continue;
+        }
+        // Smaller bodies first, these can only be subsumed by equal bodies
+        rule_ids.sort_by_key(|rule_id| aba.rules[*rule_id].1.len());
+        let mut kept: Vec<usize> = vec![];
+        for rule_id in rule_ids {
+            let body = &aba.rules[rule_id].1;
+            match kept
+                .iter()
+                .map(|other| &aba.rules[*other].1)
+                .find(|other| other.is_subset(body))
+            {
+                Some(other) if other.len() == body.len() => {
+                    report.duplicate_rules += 1;
+                    removed.insert(rule_id);
+                }
+                Some(_) => {
+                    report.subsumed_rules += 1;
+                    removed.insert(rule_id);
+                }
+                None => kept.push(rule_id),
+            }
+        }
+    }
+    let mut rule_id = 0;
+    aba.rules.retain(|_| {
+        rule_id += 1;
+        !removed.contains(rule_id - 1)
+    });
+}
+
+impl Display for Simplification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "facts: {}, out assumptions: {}, removed rules (fact: {}, attacked: {}, duplicate: {}, subsumed: {}), merged atoms: {}",
+            self.facts,
+            self.out_assumptions,
+            self.fact_rules,
+            self.attacked_rules,
+            self.duplicate_rules,
+            self.subsumed_rules,
+            self.merged_atoms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aba::debug::DebugAba;
+
+    use super::*;
+
+    #[test]
+    fn facts_are_propagated() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'q')
+            .with_rule('p', [])
+            .with_rule('p', ['b'])
+            .with_rule('r', ['p', 'b'])
+            .with_rule('r', ['q'])
+            .with_rule('s', ['a']);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let mut simplified = aba.aba().clone();
+        let (report, _) = simplify(&mut simplified);
+        assert_eq!(report.facts, 1);
+        assert_eq!(report.out_assumptions, 1);
+        assert_eq!(report.fact_rules, 1);
+        assert_eq!(report.attacked_rules, 1);
+        assert!(simplified
+            .rules
+            .contains(&(atom('r'), [atom('b')].into_iter().collect())));
+    }
+
+    #[test]
+    fn chains_are_merged_and_subsumed_rules_removed() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'q')
+            .with_rule('p', ['q'])
+            .with_rule('q', ['a', 'b'])
+            .with_rule('q', ['a']);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let mut simplified = aba.aba().clone();
+        let (report, representatives) = simplify(&mut simplified);
+        assert_eq!(report.merged_atoms, 1);
+        assert_eq!(report.subsumed_rules, 1);
+        assert_eq!(representatives.get(&atom('p')), Some(&atom('q')));
+        assert_eq!(simplified.inverses[&atom('a')], atom('q'));
+        assert_eq!(simplified.rules.len(), 1);
+    }
+}
@@ -0,0 +1,112 @@
+//! Query-driven slicing of an [`Aba`]
+//!
+//! Whether an atom is credulously accepted under the admissible or complete semantics
+//! only depends on the arguments that can attack it, the arguments attacking those, and
+//! so on. Everything outside of this backward closure can neither attack the query nor
+//! be needed to defend it, so it does not need to be encoded at all.
+//!
+//! This does not hold for semantics that require attacking everything outside of an
+//! extension, like stable, these must be solved on the full framework.
+use bit_set::BitSet;
+
+use super::{index::AbaIndex, Aba, Num};
+
+/// The part of `aba` that is relevant for the acceptance of `query`
+///
+/// Starting from `query`, all atoms that can be used to derive it are collected. For every
+/// collected assumption, the atoms that can be used to derive its contrary are added as well.
+/// The slice contains all rules for collected heads and all collected assumptions.
+///
+/// Returns `None` if `query` is neither an assumption nor the head of a rule. Such
+/// an atom can never be accepted and is left to the checks of the problem.
+#[cfg_attr(
+    feature = "timing",
+    fun_time::fun_time(message = "Slicing ABA", reporting = "log")
+)]
+pub fn relevant_slice(aba: &Aba, query: Num) -> Option<Aba> {
+    let index = AbaIndex::new(aba);
+    if !index.is_assumption(&query) && index.rules_with_head(&query).is_empty() {
+        return None;
+    }
+    let slice = slice_with_index(aba, &index, [query]);
+    log::info!(
+        "Sliced ABA for {query}: kept {} of {} rules and {} of {} assumptions",
+        slice.rules.len(),
+        aba.rules.len(),
+        slice.inverses.len(),
+        aba.inverses.len()
+    );
+    Some(slice)
+}
+
+/// The part of `aba` that is relevant for all of the `queries`
+///
+/// See [`relevant_slice`], queries that are not part of `aba` are ignored.
+pub fn relevant_slice_of<I: IntoIterator<Item = Num>>(aba: &Aba, queries: I) -> Aba {
+    slice_with_index(aba, &AbaIndex::new(aba), queries)
+}
+
+fn slice_with_index<I: IntoIterator<Item = Num>>(aba: &Aba, index: &AbaIndex, queries: I) -> Aba {
+    let mut relevant = BitSet::with_capacity(index.len());
+    let mut queue: Vec<_> = queries
+        .into_iter()
+        .filter(|query| index.contains_atom(query))
+        .collect();
+    while let Some(atom) = queue.pop() {
+        if !relevant.insert(index.position(&atom).unwrap()) {
+            continue;
+        }
+        if let Some(inverse) = aba.inverses.get(&atom) {
+            queue.push(*inverse);
+        }
+        for rule_id in index.rules_with_head(&atom) {
+            queue.extend(&aba.rules[*rule_id].1);
+        }
+    }
+    let is_relevant = |atom: &Num| relevant.contains(index.position(atom).unwrap());
+    Aba {
+        rules: aba
+            .rules
+            .iter()
+            .filter(|(head, _)| is_relevant(head))
+            .cloned()
+            .collect(),
+        inverses: aba
+            .inverses
+            .iter()
+            .filter(|(assumption, _)| is_relevant(assumption))
+            .map(|(assumption, inverse)| (*assumption, *inverse))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aba::debug::DebugAba;
+
+    use super::*;
+
+    #[test]
+    fn slice_keeps_attackers_of_attackers() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'p')
+            .with_assumption('b', 'q')
+            .with_assumption('c', 'r')
+            .with_assumption('d', 's')
+            .with_rule('p', ['b'])
+            .with_rule('q', ['c'])
+            .with_rule('t', ['d']);
+        let atom = |atom| aba.forward_atom(atom).unwrap();
+        let slice = relevant_slice(aba.aba(), atom('a')).unwrap();
+        let mut assumptions: Vec<_> = slice.assumptions().cloned().collect();
+        assumptions.sort();
+        let mut expected = vec![atom('a'), atom('b'), atom('c')];
+        expected.sort();
+        assert_eq!(assumptions, expected);
+        assert_eq!(slice.rules.len(), 2);
+        let slice = relevant_slice(aba.aba(), atom('t')).unwrap();
+        assert_eq!(slice.assumptions().collect::<Vec<_>>(), vec![&atom('d')]);
+        assert_eq!(slice.rules.len(), 1);
+        assert_eq!(relevant_slice(aba.aba(), atom('r')), None);
+    }
+}
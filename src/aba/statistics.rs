@@ -0,0 +1,59 @@
+//! Statistics collected while preparing and solving an [`Aba`](crate::aba::Aba)
+use std::fmt::Display;
+
+use super::simplify::Simplification;
+
+/// The encoding chosen for the theory derivation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// The dependency graph of the rules contains cycles, loops must be broken
+    #[default]
+    Cyclic,
+    /// There are no cycles, no loop helpers are needed and heads with a single
+    /// body are inlined instead of using a rule helper
+    Acyclic,
+}
+
+/// A rule that answered a problem without calling the SAT solver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// The contrary of the query can not be derived at all
+    Unattackable,
+    /// The contrary of the query is a fact
+    ContraryIsFact,
+    /// The query is part of or derivable from the grounded extension
+    Grounded,
+    /// The query is attacked by the grounded extension
+    Defeated,
+    /// The query can not be derived without assumptions attacked by the grounded extension
+    Underivable,
+}
+
+/// Information about the work done for a single [`PreparedAba`](crate::aba::PreparedAba)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    /// The encoding used for the theory derivation
+    pub encoding: Encoding,
+    /// Number of loops that will be broken
+    pub loops: usize,
+    /// Number of rules that do not need a rule helper
+    pub inlined_rules: usize,
+    /// Changes done to the framework before encoding it
+    pub simplification: Simplification,
+    /// The shortcut used to answer the problem, if any
+    pub shortcut: Option<Shortcut>,
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "encoding: {:?}, loops: {}, inlined rules: {}, {}",
+            self.encoding, self.loops, self.inlined_rules, self.simplification
+        )?;
+        if let Some(shortcut) = self.shortcut {
+            write!(f, ", shortcut: {shortcut:?}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,381 @@
+//! Symmetries of a [`PreparedAba`] and their use in the encoding
+//!
+//! The framework is translated into a coloured incidence graph. Atoms and rules are
+//! vertices, rules are connected to their head and body atoms and assumptions to their
+//! contrary. Every automorphism of this graph maps extensions to extensions.
+//!
+//! Generators of the automorphism group are searched using colour refinement and
+//! individualization. Every generator found is verified, so the symmetries are always
+//! sound, but the group may be incomplete.
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::{
+    clauses::Clause,
+    literal::{
+        lits::{Candidate, SymmetryHelper},
+        IntoLiteral,
+    },
+};
+
+use super::{prepared::PreparedAba, Num};
+
+/// Maximum number of refinements spent on finding a single generator
+const SEARCH_BUDGET: usize = 1_000;
+
+/// Generators of a group of symmetries on the assumptions of an [`Aba`](crate::aba::Aba)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Symmetries {
+    /// Every generator maps the assumptions it moves to their image
+    generators: Vec<HashMap<Num, Num>>,
+}
+
+/// Kind of an edge in the incidence graph, including its direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Edge {
+    HeadOf,
+    HasHead,
+    BodyOf,
+    HasBody,
+    ContraryOf,
+    HasContrary,
+}
+
+/// Coloured incidence graph of a framework
+///
+/// Vertices `0..atoms` are the atoms in the order of the index, the rules follow.
+struct Graph {
+    atoms: usize,
+    colours: Vec<usize>,
+    adjacent: Vec<Vec<(Edge, usize)>>,
+    edges: HashSet<(usize, Edge, usize)>,
+}
+
+impl Symmetries {
+    /// Find symmetries of `aba` that map every atom of `fixed` to itself
+    #[cfg_attr(
+        feature = "timing",
+        fun_time::fun_time(message = "Searching symmetries", reporting = "log")
+    )]
+    pub fn of(aba: &PreparedAba, fixed: &[Num]) -> Self {
+        let graph = Graph::new(aba, fixed);
+        let generators: Vec<_> = graph
+            .generators()
+            .into_iter()
+            .map(|perm| {
+                (0..graph.atoms)
+                    .filter(|atom| perm[*atom] != *atom)
+                    .map(|atom| aba.index().atom(atom))
+                    .filter(|atom| aba.contains_assumption(atom))
+                    .map(|atom| {
+                        (
+                            atom,
+                            aba.index().atom(perm[aba.index().position(&atom).unwrap()]),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .filter(|generator| !generator.is_empty())
+            .collect();
+        log::info!("Found {} symmetry generators", generators.len());
+        Symmetries { generators }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Number of [`SymmetryHelper`]s used by [`Symmetries::lex_leader_clauses`]
+    pub fn helper_count(&self) -> usize {
+        self.generators
+            .iter()
+            .map(|generator| generator.len())
+            .sum()
+    }
+
+    /// Clauses that only allow the lexicographically smallest set of every orbit
+    ///
+    /// For every generator `g`, the moved assumptions `a_1 < ... < a_n` are compared
+    /// with their images. With `x_i` for `a_i in th(Candidate)` and `y_i` for
+    /// `g(a_i) in th(Candidate)`, the helpers `e_i` are implied if the first `i`
+    /// positions are equal:
+    /// ```text
+    ///    e_(i-1) => (x_i => y_i)
+    ///    e_(i-1) and (x_i <=> y_i) => e_i
+    /// ```
+    /// Every orbit of the generated group contains a set satisfying these clauses.
+    pub fn lex_leader_clauses(&self) -> impl Iterator<Item = Clause> + '_ {
+        let offsets = self.generators.iter().scan(0, |offset, generator| {
+            let start = *offset;
+            *offset += generator.len();
+            Some(start)
+        });
+        self.generators
+            .iter()
+            .zip(offsets)
+            .flat_map(|(generator, offset)| {
+                let mut moved: Vec<_> = generator.iter().collect();
+                moved.sort_unstable();
+                moved
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(move |(idx, (assumption, image))| {
+                        let x = Candidate::from(*assumption);
+                        let y = Candidate::from(*image);
+                        let equal = SymmetryHelper::from(offset + idx);
+                        // The first position is always compared
+                        let previous =
+                            (idx > 0).then(|| SymmetryHelper::from(offset + idx - 1).neg());
+                        [
+                            vec![x.neg(), y.pos()],
+                            vec![x.pos(), y.pos(), equal.pos()],
+                            vec![x.neg(), y.neg(), equal.pos()],
+                        ]
+                        .into_iter()
+                        .map(move |clause| {
+                            clause
+                                .into_iter()
+                                .chain(previous.clone())
+                                .collect::<Clause>()
+                        })
+                    })
+            })
+    }
+
+    /// Add the images of all `sets` under the group to the result
+    ///
+    /// The given sets come first, followed by their images in the order they are found.
+    pub fn expand(&self, sets: Vec<HashSet<Num>>) -> Vec<HashSet<Num>> {
+        if self.is_empty() {
+            return sets;
+        }
+        let key = |set: &HashSet<Num>| set.iter().cloned().collect::<BTreeSet<_>>();
+        let mut seen: HashSet<BTreeSet<Num>> = sets.iter().map(key).collect();
+        let mut result = sets.clone();
+        let mut queue: VecDeque<_> = sets.into_iter().collect();
+        while let Some(set) = queue.pop_front() {
+            for generator in &self.generators {
+                let image: HashSet<_> = set
+                    .iter()
+                    .map(|atom| *generator.get(atom).unwrap_or(atom))
+                    .collect();
+                if seen.insert(key(&image)) {
+                    result.push(image.clone());
+                    queue.push_back(image);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Graph {
+    fn new(aba: &PreparedAba, fixed: &[Num]) -> Self {
+        let index = aba.index();
+        let atoms = index.len();
+        let vertices = atoms + aba.rules.len();
+        // Atoms and assumptions get different colours, rules a third one
+        let mut colours: Vec<usize> = (0..vertices)
+            .map(|vertex| match vertex {
+                vertex if vertex >= atoms => 2,
+                vertex if index.is_assumption(&index.atom(vertex)) => 1,
+                _ => 0,
+            })
+            .collect();
+        // Fixed atoms get a colour of their own
+        let fixed = fixed
+            .iter()
+            .filter_map(|atom| aba.representative(*atom))
+            .filter_map(|atom| index.position(&atom));
+        for (idx, position) in fixed.enumerate() {
+            colours[position] = 3 + idx;
+        }
+        let mut edges = HashSet::new();
+        for (rule_id, (head, body)) in aba.rules.iter().enumerate() {
+            let rule = atoms + rule_id;
+            edges.insert((rule, Edge::HasHead, index.position(head).unwrap()));
+            for atom in body {
+                edges.insert((rule, Edge::HasBody, index.position(atom).unwrap()));
+            }
+        }
+        for (assumption, inverse) in &aba.inverses {
+            let assumption = index.position(assumption).unwrap();
+            edges.insert((
+                assumption,
+                Edge::HasContrary,
+                index.position(inverse).unwrap(),
+            ));
+        }
+        let mut adjacent = vec![vec![]; vertices];
+        for (from, edge, to) in &edges {
+            let reverse = match edge {
+                Edge::HasHead => Edge::HeadOf,
+                Edge::HasBody => Edge::BodyOf,
+                _ => Edge::ContraryOf,
+            };
+            adjacent[*from].push((*edge, *to));
+            adjacent[*to].push((reverse, *from));
+        }
+        let mut graph = Graph {
+            atoms,
+            colours,
+            adjacent,
+            edges,
+        };
+        let mut colours = std::mem::take(&mut graph.colours);
+        graph.refine(&mut colours);
+        graph.colours = colours;
+        graph
+    }
+
+    /// Generators of the automorphism group, as permutations of the vertices
+    ///
+    /// Walks down the chain of point stabilizers. For the first vertex `v` of the first
+    /// non-singleton cell, an automorphism mapping `v` to every other vertex of its cell
+    /// is searched, unless the vertex is already in the orbit of `v`.
+    fn generators(&self) -> Vec<Vec<usize>> {
+        let mut generators: Vec<Vec<usize>> = vec![];
+        let mut current = self.colours.clone();
+        while let Some(cell) = first_cell(&current) {
+            let vertex = cell[0];
+            for other in &cell[1..] {
+                if orbit(&generators, vertex).contains(other) {
+                    continue;
+                }
+                let mut budget = SEARCH_BUDGET;
+                let left = self.individualize(&current, vertex);
+                let right = self.individualize(&current, *other);
+                if let Some(perm) = self.search(left, right, &mut budget) {
+                    generators.push(perm);
+                }
+            }
+            current = self.individualize(&current, vertex);
+        }
+        generators
+    }
+
+    /// Search an automorphism mapping the colouring `left` onto `right`
+    fn search(
+        &self,
+        left: Vec<usize>,
+        right: Vec<usize>,
+        budget: &mut usize,
+    ) -> Option<Vec<usize>> {
+        if *budget == 0 || histogram(&left) != histogram(&right) {
+            return None;
+        }
+        *budget -= 1;
+        let Some(cell) = first_cell(&left) else {
+            // Both colourings are discrete, they define the permutation
+            let mut vertex_of_colour = vec![0; right.len()];
+            for (vertex, colour) in right.iter().enumerate() {
+                vertex_of_colour[*colour] = vertex;
+            }
+            let perm: Vec<_> = left
+                .iter()
+                .map(|colour| vertex_of_colour[*colour])
+                .collect();
+            return self.is_automorphism(&perm).then_some(perm);
+        };
+        let vertex = cell[0];
+        // Try the identity first, this is the most likely candidate
+        let mut candidates: Vec<_> = (0..right.len())
+            .filter(|other| right[*other] == left[vertex])
+            .collect();
+        candidates.sort_by_key(|other| *other != vertex);
+        let left = self.individualize(&left, vertex);
+        candidates.into_iter().find_map(|other| {
+            let right = self.individualize(&right, other);
+            self.search(left.clone(), right, budget)
+        })
+    }
+
+    /// Give `vertex` a new colour and refine the result
+    fn individualize(&self, colours: &[usize], vertex: usize) -> Vec<usize> {
+        let mut colours = colours.to_vec();
+        colours[vertex] = colours.len();
+        self.refine(&mut colours);
+        colours
+    }
+
+    /// Refine the colouring until it is equitable
+    ///
+    /// New colours are the position of the signature in the sorted list of all
+    /// signatures. Isomorphic colourings are refined to the same colours.
+    fn refine(&self, colours: &mut [usize]) {
+        let mut count = histogram(colours).len();
+        loop {
+            let signatures: Vec<_> = (0..colours.len())
+                .map(|vertex| {
+                    let mut neighbours: Vec<_> = self.adjacent[vertex]
+                        .iter()
+                        .map(|(edge, other)| (*edge, colours[*other]))
+                        .collect();
+                    neighbours.sort_unstable();
+                    (colours[vertex], neighbours)
+                })
+                .collect();
+            let mut sorted: Vec<_> = signatures.iter().collect();
+            sorted.sort_unstable();
+            sorted.dedup();
+            let ids: HashMap<_, _> = sorted
+                .into_iter()
+                .enumerate()
+                .map(|(id, signature)| (signature, id))
+                .collect();
+            for (vertex, signature) in signatures.iter().enumerate() {
+                colours[vertex] = ids[signature];
+            }
+            if ids.len() == count {
+                break;
+            }
+            count = ids.len();
+        }
+    }
+
+    fn is_automorphism(&self, perm: &[usize]) -> bool {
+        perm.iter()
+            .enumerate()
+            .all(|(vertex, image)| self.colours[vertex] == self.colours[*image])
+            && self
+                .edges
+                .iter()
+                .all(|(from, edge, to)| self.edges.contains(&(perm[*from], *edge, perm[*to])))
+    }
+}
+
+/// Vertices of the smallest colour shared by multiple vertices
+fn first_cell(colours: &[usize]) -> Option<Vec<usize>> {
+    let counts = histogram(colours);
+    let colour = counts
+        .iter()
+        .filter(|(_, count)| **count > 1)
+        .map(|(colour, _)| *colour)
+        .min()?;
+    Some(
+        (0..colours.len())
+            .filter(|vertex| colours[*vertex] == colour)
+            .collect(),
+    )
+}
+
+/// Number of vertices per colour
+fn histogram(colours: &[usize]) -> HashMap<usize, usize> {
+    colours.iter().fold(HashMap::new(), |mut counts, colour| {
+        *counts.entry(*colour).or_default() += 1;
+        counts
+    })
+}
+
+/// All vertices `vertex` can be mapped to using the `generators`
+fn orbit(generators: &[Vec<usize>], vertex: usize) -> HashSet<usize> {
+    let mut orbit = HashSet::from([vertex]);
+    let mut queue = vec![vertex];
+    while let Some(vertex) = queue.pop() {
+        for generator in generators {
+            if orbit.insert(generator[vertex]) {
+                queue.push(generator[vertex]);
+            }
+        }
+    }
+    orbit
+}
@@ -1,5 +1,3 @@
-use std::collections::{HashMap, HashSet};
-
 use crate::{clauses::Clause, literal::IntoLiteral};
 
 use super::{prepared::PreparedAba, Context};
@@ -22,52 +20,53 @@ use super::{prepared::PreparedAba, Context};
 /// # Extra steps
 ///
 /// - We do some extra work here to prevent atoms that never occur in the head of rule and
-/// are not an assumption from ever being true.
+///   are not an assumption from ever being true.
 /// - heads with a single body are common enough in practice to benefit from special care.
 ///   A lot of the overhead is due to the fact that multiple bodies are an option, if that's
 ///   not given for a head `p` we use the simplified translation logic where `p` is true iff
-///   `bodies(p)` is true.
-pub fn theory_helper<Ctx: Context>(aba: &PreparedAba) -> impl Iterator<Item = Clause> + '_ {
-    // The combined list of rules, such that every
-    // head is unique and possible contains a list of body rule ids
-    let mut rules_combined = aba.rules.iter().enumerate().fold(
-        HashMap::<_, Vec<_>>::new(),
-        |mut rules, (rule_id, (head, _body))| {
-            rules.entry(head).or_default().push(rule_id);
-            rules
-        },
-    );
-    // All atoms that can be derived by rules
-    let rule_heads: HashSet<_> = aba.rule_heads().collect();
-    // For every non-assumption, that is not derivable add a rule without a body,
+///   `bodies(p)` is true. For acyclic frameworks, the rule helper is skipped entirely and `p`
+///   is defined directly by its body.
+pub fn theory_helper(aba: &PreparedAba, ctx: Context) -> impl Iterator<Item = Clause> + '_ {
+    let index = aba.index();
+    // Every head is unique and possibly contains a list of body rule ids.
+    // For every non-assumption, that is not derivable the list is empty,
     // such that it cannot be derived at all. This is to prevent the solver from
     // guessing this atom on it's own
-    aba.universe()
-        .filter(|atom| !aba.contains_assumption(atom))
-        .filter(|atom| !rule_heads.contains(atom))
-        .map(|atom| (atom, vec![]))
-        .collect_into(&mut rules_combined);
-    // All combined rules
-    // These are heads with any number of bodies, possibly none
-    rules_combined
-        .into_iter()
-        .flat_map(|(head, rule_ids)| match &rule_ids[..] {
+    index
+        .atoms()
+        .iter()
+        .map(|atom| (atom, index.rules_with_head(atom)))
+        .filter(|(atom, rule_ids)| !rule_ids.is_empty() || !index.is_assumption(atom))
+        // All combined rules
+        // These are heads with any number of bodies, possibly none
+        .flat_map(move |(head, rule_ids)| match rule_ids {
             // No bodies, add a clause that prevents the head from accuring in the theory
             [] => {
-                vec![Clause::from(vec![Ctx::Base::from(*head).neg()])]
+                vec![Clause::from(vec![ctx.atom(*head).neg()])]
+            }
+            // A single body that is inlined, the head is directly defined by the body
+            // ```text
+            //    H <=> b_1 and ... and b_n
+            // ⋄  (-H or b_1) and ... and (-H or b_n) and (H or -b_1 or ... or -b_n)
+            // ```
+            [rule_id] if aba.is_inlined(*rule_id) => {
+                let body = &aba.rules[*rule_id].1;
+                let last_clause = body
+                    .iter()
+                    .map(|el| ctx.atom(*el).neg())
+                    .chain(std::iter::once(ctx.atom(*head).pos()))
+                    .collect();
+                body.iter()
+                    .map(|el| Clause::from(vec![ctx.atom(*head).neg(), ctx.atom(*el).pos()]))
+                    .chain([last_clause])
+                    .collect()
             }
             // A single body only, this is equivalent to a head that can only be derived by a single rule
             // H <=> RBA_rule_id
             [rule_id] => {
                 vec![
-                    Clause::from(vec![
-                        Ctx::Base::from(*head).pos(),
-                        Ctx::Rule::from(*rule_id).neg(),
-                    ]),
-                    Clause::from(vec![
-                        Ctx::Base::from(*head).neg(),
-                        Ctx::Rule::from(*rule_id).pos(),
-                    ]),
+                    Clause::from(vec![ctx.atom(*head).pos(), ctx.rule(*rule_id).neg()]),
+                    Clause::from(vec![ctx.atom(*head).neg(), ctx.rule(*rule_id).pos()]),
                 ]
             }
             // n bodies for this head
@@ -80,16 +79,13 @@ pub fn theory_helper<Ctx: Context>(aba: &PreparedAba) -> impl Iterator<Item = Cl
                 rule_ids
                     .iter()
                     .map(|rule_id| {
-                        Clause::from(vec![
-                            Ctx::Base::from(*head).pos(),
-                            Ctx::Rule::from(*rule_id).neg(),
-                        ])
+                        Clause::from(vec![ctx.atom(*head).pos(), ctx.rule(*rule_id).neg()])
                     })
                     .collect_into(&mut clauses);
                 let last_clause = rule_ids
                     .iter()
-                    .map(|rule_id| Ctx::Rule::from(*rule_id).pos())
-                    .chain(std::iter::once(Ctx::Base::from(*head).neg()))
+                    .map(|rule_id| ctx.rule(*rule_id).pos())
+                    .chain(std::iter::once(ctx.atom(*head).neg()))
                     .collect();
                 clauses.push(last_clause);
                 clauses
@@ -1,26 +1,46 @@
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::{
+    collections::{btree_set, BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
 
 use bit_set::BitSet;
 
-use crate::STOP_LOOP_COUNTING;
+use crate::cancel::CancellationToken;
 
 use super::{Aba, Num, RuleList};
 
 pub struct Loops {
-    rem_loops: Option<usize>,
-    sccs: Vec<Graph>,
-    rem: Vec<BitSet<usize>>,
+    loops: Vec<BitSet<usize>>,
 }
 
 impl Loops {
     pub fn of(aba: &'_ Aba, max_loops: Option<usize>) -> Self {
-        // Set the global stopper to false;
-        STOP_LOOP_COUNTING.store(false, std::sync::atomic::Ordering::Relaxed);
-        Self {
-            rem_loops: max_loops,
-            sccs: Graph::compute_sccs(aba).collect(),
-            rem: vec![],
+        Self::until(aba, max_loops, None, &CancellationToken::new())
+    }
+
+    /// Like [`Loops::of`], but stop searching once `deadline` passed or `cancel`
+    /// is cancelled
+    ///
+    /// The loops found until then are kept, like with a smaller maximum.
+    pub fn until(
+        aba: &'_ Aba,
+        max_loops: Option<usize>,
+        deadline: Option<Instant>,
+        cancel: &CancellationToken,
+    ) -> Self {
+        let sccs: Vec<_> = Graph::compute_sccs(aba).collect();
+        let budget = Budget::new(max_loops, deadline, cancel.clone());
+        let loops = compute_loops_in_parallel(&sccs, &budget);
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            log::warn!("Loop enumeration timed out after {} loops", loops.len());
+        } else if cancel.is_cancelled() {
+            log::warn!("Loop enumeration cancelled after {} loops", loops.len());
         }
+        Self { loops }
     }
 }
 
@@ -42,6 +62,50 @@ struct Graph {
     /// An edge (s, t) exists if there is a rule t <- B with s in B.
     next: BTreeMap<Num, BTreeSet<Num>>,
 }
+
+/// Limits of the loop search shared by all SCCs
+///
+/// The user given maximum applies to the sum of the loops of all SCCs, see
+/// [`compute_loops_in_parallel`].
+struct Budget {
+    max: usize,
+    /// Loops found by all searches so far
+    found: AtomicUsize,
+    deadline: Option<Instant>,
+    cancel: CancellationToken,
+}
+
+/// Loops of a single SCC
+struct Search {
+    loops: Vec<BitSet<usize>>,
+    /// Whether all loops were found, without reaching a limit
+    exhaustive: bool,
+}
+
+impl Budget {
+    fn new(max_loops: Option<usize>, deadline: Option<Instant>, cancel: CancellationToken) -> Self {
+        Budget {
+            max: max_loops.unwrap_or(usize::MAX),
+            found: AtomicUsize::new(0),
+            deadline,
+            cancel,
+        }
+    }
+
+    /// Whether the loops found by all searches together reached the maximum
+    fn exhausted(&self) -> bool {
+        self.found.load(Ordering::Relaxed) >= self.max
+    }
+
+    /// Whether the search for loops should end early
+    fn stopped(&self) -> bool {
+        self.cancel.is_cancelled()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
 impl Graph {
     fn compute_sccs(aba: &Aba) -> impl Iterator<Item = Self> + '_ {
         compute_sccs(&aba.rules)
@@ -63,8 +127,11 @@ impl Graph {
             .map(|next| Graph { next })
     }
 
-    fn compute_loops(&self, max_loops: Option<usize>) -> Vec<BitSet<usize>> {
-        let max_loops = max_loops.unwrap_or(usize::MAX);
+    /// Search up to `cap` loops of this SCC
+    ///
+    /// A `shared` search also stops once the `budget` is [exhausted](Budget::exhausted)
+    /// by the searches of all SCCs together.
+    fn compute_loops(&self, cap: usize, budget: &Budget, shared: bool) -> Search {
         #[derive(Debug)]
         struct Frame {
             node: Num,
@@ -78,12 +145,21 @@ impl Graph {
             }
         }];
         let mut loops: Vec<BitSet<usize>> = vec![];
+        let full = |loops: &Vec<BitSet<usize>>| {
+            loops.len() >= cap || budget.stopped() || (shared && budget.exhausted())
+        };
+        let mut exhaustive = true;
+        let push = |loops: &mut Vec<BitSet<usize>>, new_loop| {
+            loops.push(new_loop);
+            budget.found.fetch_add(1, Ordering::Relaxed);
+        };
         // Begin!
         loop {
-            if stack.is_empty()
-                || loops.len() >= max_loops
-                || STOP_LOOP_COUNTING.load(std::sync::atomic::Ordering::Relaxed)
-            {
+            if stack.is_empty() {
+                break;
+            }
+            if full(&loops) {
+                exhaustive = false;
                 break;
             }
             // Push to the stack!
@@ -120,9 +196,9 @@ impl Graph {
                 Some((idx, _)) => {
                     // We have found a loop!
                     let new_loop = stack[idx + 1..].iter().map(|f| f.node as usize).collect();
-                    if !loops.iter().any(|l| *l == new_loop) {
+                    if !loops.contains(&new_loop) {
                         // It's a novel loop!
-                        loops.push(new_loop);
+                        push(&mut loops, new_loop);
                     }
                     // drop the current frame
                     stack.pop();
@@ -136,7 +212,11 @@ impl Graph {
         // calculate joined loops
         let mut left_idx = 0;
         // the left index will walk once through all loops, including new ones
-        'outer: while left_idx < loops.len() && loops.len() < max_loops {
+        'outer: while exhaustive && left_idx < loops.len() {
+            if full(&loops) {
+                exhaustive = false;
+                break;
+            }
             // the right index will walk through the entire list for every left index
             for right_idx in 0..loops.len() {
                 if left_idx == right_idx {
@@ -152,17 +232,18 @@ impl Graph {
                     // make new the union of left and right
                     new.union_with(left);
                     new.union_with(right);
-                    if !loops.iter().any(|l| new == *l) {
-                        loops.push(new)
+                    if !loops.contains(&new) {
+                        push(&mut loops, new)
                     }
-                    if loops.len() >= max_loops {
+                    if full(&loops) {
+                        exhaustive = false;
                         break 'outer;
                     }
                 }
             }
             left_idx += 1;
         }
-        loops
+        Search { loops, exhaustive }
     }
 
     /// A random node from this graph
@@ -172,6 +253,70 @@ impl Graph {
     }
 }
 
+/// Compute the loops of all `sccs` using all available cores
+///
+/// The result is the same as searching the SCCs one after another, each with
+/// the part of the maximum of the `budget` the previous ones left, so it does
+/// not depend on the scheduling of the workers.
+///
+/// SCCs do not share any loops, so every worker grabs the next SCC that has
+/// not been started yet. All workers stop once they found the maximum together.
+/// The results are then taken in SCC order. A search that was cut short by the
+/// loops of the other SCCs is repeated with the budget left at its position, so
+/// the loop search stays bounded by about twice the maximum.
+fn compute_loops_in_parallel(sccs: &[Graph], budget: &Budget) -> Vec<BitSet<usize>> {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(sccs.len());
+    let next_scc = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Search>>> = sccs.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let idx = next_scc.fetch_add(1, Ordering::Relaxed);
+                if idx >= sccs.len() || budget.stopped() || budget.exhausted() {
+                    break;
+                }
+                let search = sccs[idx].compute_loops(budget.max, budget, true);
+                *results[idx].lock().unwrap() = Some(search);
+            });
+        }
+    });
+    let mut remaining = budget.max;
+    let mut loops = vec![];
+    for (scc, search) in sccs.iter().zip(results) {
+        if remaining == 0 {
+            break;
+        }
+        let found = match search.into_inner().unwrap() {
+            // The search would have ended the same way with the budget left
+            Some(search)
+                if search.loops.len() == remaining
+                    || (search.exhaustive && search.loops.len() < remaining) =>
+            {
+                search.loops
+            }
+            // Out of time, keep what was found
+            Some(mut search) if budget.stopped() => {
+                search.loops.truncate(remaining);
+                search.loops
+            }
+            _ => scc.compute_loops(remaining, budget, false).loops,
+        };
+        remaining -= found.len();
+        loops.extend(found);
+    }
+    loops
+}
+
+/// Whether any atom of the [`Aba`] can be used to derive itself
+///
+/// Rules containing their head in the body are not considered.
+pub fn has_cycles(aba: &Aba) -> bool {
+    compute_sccs(&aba.rules).iter().any(|scc| scc.len() >= 2)
+}
+
 fn compute_sccs(rules: &RuleList) -> Vec<BTreeSet<Num>> {
     // We're only interested in atoms that can be reached via a rule.
     // Since every head may have multiple rules, we join their bodies here.
@@ -179,12 +324,21 @@ fn compute_sccs(rules: &RuleList) -> Vec<BTreeSet<Num>> {
     // invert that, since sccs are unchanged by a flip of the edge and we
     // already have all the info on hand
     let succ = rules.iter().fold(
-        BTreeMap::<Num, HashSet<Num>>::new(),
+        BTreeMap::<Num, BTreeSet<Num>>::new(),
         |mut map, (head, body)| {
             map.entry(*head).or_default().extend(body);
             map
         },
     );
+    strongly_connected_components(&succ)
+}
+
+/// Tarjan's algorithm on the graph given by the successors `succ`
+///
+/// Every SCC is returned after all SCCs reachable from it.
+pub(super) fn strongly_connected_components(
+    succ: &BTreeMap<Num, BTreeSet<Num>>,
+) -> Vec<BTreeSet<Num>> {
     let mut sccs = vec![];
     // current tarjan index
     let mut index = 0;
@@ -197,79 +351,88 @@ fn compute_sccs(rules: &RuleList) -> Vec<BTreeSet<Num>> {
         lowlink: usize,
         on_stack: bool,
     }
+    /// A suspended call of Tarjan's strongconnect function
+    ///
+    /// The recursion is replaced by an explicit stack of these, since long
+    /// rule chains would otherwise overflow the call stack.
+    struct Call<'s> {
+        node: Num,
+        // an extra check is necessary here, as some atoms may not be
+        // head to a rule, like assumptions
+        successors: Option<btree_set::Iter<'s, Num>>,
+    }
     // information about all elements of our "graph"
     let mut info: BTreeMap<Num, Info> = BTreeMap::new();
-    // Tarjans strongconnect function
-    fn strong_connect(
-        sccs: &mut Vec<BTreeSet<Num>>,
-        succ: &BTreeMap<Num, HashSet<Num>>,
-        info: &mut BTreeMap<Num, Info>,
-        stack: &mut Vec<Num>,
-        index: &mut usize,
-        node: Num,
-    ) {
-        // Set the node's index and lowlink and on_stack
+    // Set the node's index and lowlink and on_stack and push it onto the stack
+    let mut visit = |info: &mut BTreeMap<Num, Info>, stack: &mut Vec<Num>, node: Num| {
         info.insert(
             node,
             Info {
-                index: *index,
-                lowlink: *index,
+                index,
+                lowlink: index,
                 on_stack: true,
             },
         );
-        *index += 1;
-        // Push the node onto the stack
+        index += 1;
         stack.push(node);
-        // an extra check is necessary here, as some atoms may not be
-        // head to a rule, like assumptions
-        let successors = match succ.get(&node) {
-            Some(successor) => successor,
-            None => {
-                return;
-            }
-        };
-        // Iterate over successor nodes
-        for successor in successors {
-            match info.get(successor) {
-                // Not yet visited
-                None => {
-                    strong_connect(sccs, succ, info, stack, index, *successor);
-                    let successor_lowlink = info.get(successor).unwrap().lowlink;
-                    let node_info = info.get_mut(&node).unwrap();
-                    node_info.lowlink = node_info.lowlink.min(successor_lowlink);
-                }
-                // Visited and in the same SCC
-                Some(i) if i.on_stack => {
-                    let successor_index = info.get(successor).unwrap().index;
-                    let node_info = info.get_mut(&node).unwrap();
-                    node_info.lowlink = node_info.lowlink.min(successor_index);
-                }
-                // Already part of another SCC
-                _ => {}
-            }
+        Call {
+            node,
+            successors: succ.get(&node).map(|successors| successors.iter()),
         }
-        let node_info = info.get(&node).unwrap();
-        // This is the root node of the SCC
-        if node_info.lowlink == node_info.index {
-            // create a new SCC from the stack
-            let mut scc = BTreeSet::new();
-            loop {
-                // Remove the SCC from the stack
-                let popped = stack.pop().unwrap();
-                scc.insert(popped);
-                let popped_info = info.get_mut(&popped).unwrap();
-                popped_info.on_stack = false;
-                if popped == node {
-                    break;
+    };
+    // iterate over all elements and their successors
+    for root in succ.keys() {
+        if info.contains_key(root) {
+            continue;
+        }
+        let mut calls = vec![visit(&mut info, &mut stack, *root)];
+        while let Some(call) = calls.last_mut() {
+            let node = call.node;
+            // Iterate over successor nodes
+            match call.successors.as_mut().and_then(|iter| iter.next()) {
+                Some(successor) => match info.get(successor) {
+                    // Not yet visited, descend into it
+                    None => {
+                        let call = visit(&mut info, &mut stack, *successor);
+                        calls.push(call);
+                    }
+                    // Visited and in the same SCC
+                    Some(i) if i.on_stack => {
+                        let successor_index = i.index;
+                        let node_info = info.get_mut(&node).unwrap();
+                        node_info.lowlink = node_info.lowlink.min(successor_index);
+                    }
+                    // Already part of another SCC
+                    _ => {}
+                },
+                // All successors handled, finish this call
+                None => {
+                    calls.pop();
+                    let node_info = info.get(&node).unwrap();
+                    let node_lowlink = node_info.lowlink;
+                    // This is the root node of the SCC
+                    if node_info.lowlink == node_info.index {
+                        // create a new SCC from the stack
+                        let mut scc = BTreeSet::new();
+                        loop {
+                            // Remove the SCC from the stack
+                            let popped = stack.pop().unwrap();
+                            scc.insert(popped);
+                            let popped_info = info.get_mut(&popped).unwrap();
+                            popped_info.on_stack = false;
+                            if popped == node {
+                                break;
+                            }
+                        }
+                        sccs.push(scc)
+                    }
+                    // Propagate the lowlink to the caller
+                    if let Some(parent) = calls.last() {
+                        let parent_info = info.get_mut(&parent.node).unwrap();
+                        parent_info.lowlink = parent_info.lowlink.min(node_lowlink);
+                    }
                 }
             }
-            sccs.push(scc)
-        }
-    }
-    // iterate over all elements and their successors
-    for node in succ.keys() {
-        if !info.contains_key(node) {
-            strong_connect(&mut sccs, &succ, &mut info, &mut stack, &mut index, *node)
         }
     }
     // Return the strongly connected components
@@ -280,23 +443,9 @@ impl Iterator for Loops {
     type Item = Loop;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.rem_loops == Some(0) {
-            return None;
-        }
-        match self.rem.pop() {
-            Some(heads) => {
-                self.rem_loops = self.rem_loops.map(|rem| rem - 1);
-                Some(Loop {
-                    heads: heads.into_iter().map(|raw| raw as Num).collect(),
-                })
-            }
-            None if self.sccs.is_empty() => None,
-            None => {
-                let next = self.sccs.pop().unwrap();
-                self.rem = next.compute_loops(self.rem_loops);
-                self.next()
-            }
-        }
+        self.loops.pop().map(|heads| Loop {
+            heads: heads.into_iter().map(|raw| raw as Num).collect(),
+        })
     }
 }
 
@@ -313,10 +462,10 @@ mod tests {
         assert_eq!(loops, 0);
 
         let mut loops = Loops::of(&aba, None);
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
     }
 
     #[test]
@@ -334,11 +483,11 @@ mod tests {
         let loops = Loops::of(aba.aba(), None).count();
         assert_eq!(loops, 1);
 
-        let mut loops = Loops::of(&aba.aba(), None);
+        let mut loops = Loops::of(aba.aba(), None);
         let first = loops.next().unwrap();
         assert_eq!(first.heads, the_loop);
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
     }
 
     #[test]
@@ -363,13 +512,13 @@ mod tests {
         let loops = Loops::of(aba.aba(), None).count();
         assert_eq!(loops, 2);
 
-        let mut loops = Loops::of(&aba.aba(), None);
+        let mut loops = Loops::of(aba.aba(), None);
         let next = loops.next().unwrap();
         assert!(next.heads == first_loop || next.heads == second_loop);
         let next = loops.next().unwrap();
         assert!(next.heads == first_loop || next.heads == second_loop);
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
     }
 
     #[test]
@@ -396,7 +545,7 @@ mod tests {
                 .into_iter()
                 .collect(),
         ];
-        let mut loops = Loops::of(&aba.aba(), None);
+        let mut loops = Loops::of(aba.aba(), None);
         for _number in 0..expected.len() {
             let next = loops.next().unwrap();
             assert!(
@@ -406,8 +555,8 @@ mod tests {
             );
         }
         // The iterator should be empty now
-        assert!(matches!(loops.next(), None));
-        assert!(matches!(loops.next(), None));
+        assert!(loops.next().is_none());
+        assert!(loops.next().is_none());
     }
 
     #[test]
@@ -426,4 +575,76 @@ mod tests {
         ];
         assert_eq!(expected, scc_indizes);
     }
+
+    #[test]
+    fn scc_of_a_long_chain() {
+        // This used to overflow the stack with a recursive implementation
+        let length = 200_000;
+        let aba = (1..length).fold(Aba::default(), |aba, atom| aba.with_rule(atom + 1, [atom]));
+        let aba = aba.with_rule(1, [length]);
+        let sccs = compute_sccs(&aba.rules);
+        assert_eq!(sccs.len(), 1);
+        assert_eq!(sccs[0].len(), length as usize);
+    }
+
+    #[test]
+    fn max_loops_is_shared_between_sccs() {
+        let aba = DebugAba::default()
+            .with_rule('p', ['q'])
+            .with_rule('q', ['p'])
+            .with_rule('u', ['v'])
+            .with_rule('v', ['u'])
+            .with_rule('x', ['y'])
+            .with_rule('y', ['x']);
+        assert_eq!(Loops::of(aba.aba(), None).count(), 3);
+        assert_eq!(Loops::of(aba.aba(), Some(2)).count(), 2);
+        assert_eq!(Loops::of(aba.aba(), Some(0)).count(), 0);
+        // The kept loops do not depend on the order the SCCs are searched in
+        let first: Vec<_> = Loops::of(aba.aba(), Some(2)).collect();
+        for _ in 0..16 {
+            assert_eq!(Loops::of(aba.aba(), Some(2)).collect::<Vec<_>>(), first);
+        }
+        // Many SCCs with four loops each, not every one of them searches the maximum
+        let many = (0..64u32).fold(DebugAba::default(), |aba, scc| {
+            let atoms = [0, 1, 2].map(|atom| char::from_u32(0x100 + scc * 3 + atom).unwrap());
+            atoms.iter().fold(aba, |aba, head| {
+                atoms
+                    .iter()
+                    .filter(|body| *body != head)
+                    .fold(aba, |aba, body| aba.with_rule(*head, [*body]))
+            })
+        });
+        let sccs: Vec<_> = Graph::compute_sccs(many.aba()).collect();
+        assert_eq!(sccs.len(), 64);
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        for max in [1, 5, 10, 40] {
+            let budget = Budget::new(Some(max), None, CancellationToken::new());
+            let loops = compute_loops_in_parallel(&sccs, &budget);
+            assert_eq!(loops.len(), max);
+            assert!(budget.found.into_inner() <= 2 * max + workers);
+            // Like a search of one SCC after another with the budget left
+            let sequential = Budget::new(Some(max), None, CancellationToken::new());
+            let expected = sccs.iter().fold(vec![], |mut loops, scc| {
+                let remaining = max - loops.len();
+                loops.extend(scc.compute_loops(remaining, &sequential, false).loops);
+                loops
+            });
+            assert_eq!(loops, expected);
+        }
+        // A deadline in the past stops the search before the first loop
+        assert_eq!(
+            Loops::until(
+                aba.aba(),
+                None,
+                Some(Instant::now()),
+                &CancellationToken::new()
+            )
+            .count(),
+            0
+        );
+        // So does a cancelled token
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert_eq!(Loops::until(aba.aba(), None, None, &cancel).count(), 0);
+    }
 }
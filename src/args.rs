@@ -1,6 +1,14 @@
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use clap::{Parser, Subcommand};
+use aba2sat::{aba::problems::limits::Limits, clauses::ClauseSimplification};
+
+#[cfg(feature = "cadical")]
+use aba2sat::sat::cadical::CadicalOptions;
+use aba2sat::sat::external::ExternalSolver;
+use clap::{Parser, Subcommand, ValueEnum};
 use lazy_static::lazy_static;
 
 lazy_static! {
@@ -29,11 +37,229 @@ pub struct Args {
     /// Will use the number of atoms by default.
     #[arg(long, short = 'l', value_name = "COUNT")]
     pub max_loops: Option<usize>,
+    /// Compute the grounded extension first and fix the assumptions it decides
+    #[arg(long, short = 'g')]
+    pub grounded: bool,
+    /// Encode the framework as it is given, without simplifying it first
+    #[arg(long)]
+    pub no_simplify: bool,
+    /// Always call the SAT solver, even if the answer can be found without it
+    #[arg(long)]
+    pub no_shortcuts: bool,
+    /// Enumerate and count extensions component by component of the attack graph
+    #[arg(long)]
+    pub split: bool,
+    /// Enumerate and count complete extensions on this many threads
+    #[arg(long, value_name = "COUNT", default_value_t = 1)]
+    pub threads: usize,
+    /// Simplification applied to the clauses before solving
+    #[arg(long, value_enum, default_value_t = ClauseSimplification::default())]
+    pub clauses: ClauseSimplification,
+    /// Exclude symmetric solutions from the search and add them back to the result
+    #[arg(long)]
+    pub symmetries: bool,
+    /// SAT solver used to solve the problem
+    #[arg(long, value_enum, default_value_t = SolverBackend::default())]
+    pub backend: SolverBackend,
+    /// Command running the external solver, the DIMACS file is appended as last argument.
+    /// Arguments are split like a shell does, quotes keep spaces.
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        value_parser = parse_command,
+        required_if_eq("backend", "external")
+    )]
+    pub external_solver: Option<ExternalSolver>,
+    /// Run COUNT solver instances in parallel and use the first answer.
+    /// CaDiCaL instances use different seeds, presets and phases.
+    #[arg(long, value_name = "COUNT")]
+    pub portfolio: Option<usize>,
+    /// Random seed of CaDiCaL
+    #[cfg(feature = "cadical")]
+    #[arg(long)]
+    pub seed: Option<u32>,
+    /// Predefined configuration of CaDiCaL
+    #[cfg(feature = "cadical")]
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+    /// Initial phase of all variables in CaDiCaL
+    #[cfg(feature = "cadical")]
+    #[arg(long)]
+    pub phase: Option<bool>,
+    /// Give up after this many seconds, exits with status 124.
+    /// Enumerations print the extensions found until then.
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    pub timeout: Option<Duration>,
+    /// Stop searching loops after this many seconds and use the loops found
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    pub loop_timeout: Option<Duration>,
+    /// Give up if the solver calls of a problem take more than this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    pub solve_timeout: Option<Duration>,
+    /// Give up if a single solver call runs into this many conflicts
+    #[arg(long, value_name = "COUNT")]
+    pub conflict_limit: Option<u32>,
+    /// Write a DRAT proof of the solver call to PATH, the formula to PATH.cnf and the
+    /// variables to PATH.map. Implies --no-shortcuts
+    #[arg(long, value_name = "PATH")]
+    pub proof: Option<PathBuf>,
+    /// Check every printed extension and verification answer without the SAT solver,
+    /// as well as the extensions behind counts and acceptance. Fail if one is wrong
+    #[arg(long)]
+    pub certify: bool,
+}
+
+impl Args {
+    /// The limits given on the command line, the timeout starts now
+    pub fn limits(&self) -> Limits {
+        Limits {
+            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            loops: self.loop_timeout,
+            solving: self.solve_timeout,
+            conflicts: self.conflict_limit,
+        }
+    }
+
+    /// The selected SAT solver backend
+    pub fn backend(&self) -> aba2sat::sat::SolverBackend {
+        match self.portfolio {
+            Some(count) if count > 1 => aba2sat::sat::SolverBackend::Portfolio(
+                (0..count).map(|idx| self.single_backend(idx)).collect(),
+            ),
+            _ => self.single_backend(0),
+        }
+    }
+
+    /// The backend of worker `idx` of a portfolio
+    fn single_backend(&self, #[allow(unused_variables)] idx: usize) -> aba2sat::sat::SolverBackend {
+        match self.backend {
+            #[cfg(feature = "cadical")]
+            SolverBackend::Cadical => {
+                let options = CadicalOptions {
+                    seed: self.seed,
+                    preset: self.preset.map(Into::into),
+                    phase: self.phase,
+                };
+                aba2sat::sat::SolverBackend::Cadical(options.diversified(idx))
+            }
+            #[cfg(feature = "cdcl")]
+            SolverBackend::Cdcl => aba2sat::sat::SolverBackend::Cdcl,
+            SolverBackend::External => aba2sat::sat::SolverBackend::External(
+                self.external_solver
+                    .clone()
+                    .expect("clap requires an external solver for the external backend"),
+            ),
+        }
+    }
+}
+
+/// Split the command of an external solver into the program and its arguments
+fn parse_command(command: &str) -> Result<ExternalSolver, String> {
+    let mut words = shlex::split(command)
+        .ok_or_else(|| String::from("unbalanced quotes or trailing backslash"))?
+        .into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| String::from("the command is empty"))?;
+    Ok(ExternalSolver {
+        program: program.into(),
+        args: words.collect(),
+    })
+}
+
+/// Parse a number of seconds, deadlines this far in the future must be representable
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    let seconds: f64 = seconds.parse().map_err(|why| format!("{why}"))?;
+    let duration = Duration::try_from_secs_f64(seconds).map_err(|why| format!("{why}"))?;
+    Instant::now()
+        .checked_add(duration)
+        .ok_or_else(|| String::from("the duration is too long"))?;
+    Ok(duration)
+}
+
+#[cfg(feature = "cadical")]
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Preset {
+    /// Default options
+    Default,
+    /// Disable all preprocessing
+    Plain,
+    /// Target satisfiable instances
+    Sat,
+    /// Target unsatisfiable instances
+    Unsat,
+}
+
+#[cfg(feature = "cadical")]
+impl From<Preset> for aba2sat::sat::cadical::Preset {
+    fn from(value: Preset) -> Self {
+        match value {
+            Preset::Default => Self::Default,
+            Preset::Plain => Self::Plain,
+            Preset::Sat => Self::Sat,
+            Preset::Unsat => Self::Unsat,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    /// A table with one row per assumption
+    Text,
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum SolverBackend {
+    /// The CaDiCaL solver, linked into the binary
+    #[cfg(feature = "cadical")]
+    #[default]
+    Cadical,
+    /// The solver written in Rust, slower than CaDiCaL
+    #[cfg(feature = "cdcl")]
+    #[cfg_attr(not(feature = "cadical"), default)]
+    Cdcl,
+    /// Any solver binary, see `--external-solver`
+    External,
 }
 
-#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Subcommand)]
 pub enum Problems {
+    /// Problems solved by the clauses of a single first solver call, these can
+    /// also be printed by [`Problems::Encode`]
+    #[command(flatten)]
+    Encodable(EncodableProblem),
+    #[clap(visible_alias = "ce-co")]
+    CountComplete,
+    /// Print the assumptions credulously accepted under complete semantics, followed by the
+    /// skeptically accepted ones
+    #[clap(visible_alias = "acc-co")]
+    AcceptanceComplete,
+    /// Print the assumptions credulously accepted under stable semantics, followed by the
+    /// skeptically accepted ones
+    #[clap(visible_alias = "acc-st")]
+    AcceptanceStable,
+    /// Print the assumptions credulously accepted under preferred semantics, followed by the
+    /// skeptically accepted ones
+    #[clap(visible_alias = "acc-pr")]
+    AcceptancePreferred,
+    /// Compare the acceptance of every assumption under all semantics.
+    /// Cells show `c` for credulous and `s` for skeptical acceptance.
+    Report {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Print the clauses of the first solver call of a problem in DIMACS format,
+    /// with a comment naming the literal of every variable
+    Encode {
+        #[command(subcommand)]
+        problem: EncodableProblem,
+    },
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Subcommand)]
+pub enum EncodableProblem {
     #[clap(visible_alias = "ve-ad")]
     VerifyAdmissibility {
         #[arg(long, short = 's', required = true)]
@@ -1,10 +1,10 @@
 use aba2sat::{
     aba::{Aba, Loops},
-    parser, STOP_LOOP_COUNTING,
+    parser, CancellationToken,
 };
 use std::path::PathBuf;
 
-use clap::{command, Parser};
+use clap::Parser;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -37,19 +37,26 @@ pub enum Error {
     ParsingAbaFile(#[from] aba2sat::Error),
 }
 
+#[cfg(test)]
 fn count_loops(aba: &Aba, max_loops: Option<usize>) -> usize {
     Loops::of(aba, max_loops).count()
 }
 
+/// Count the loops like [`Loops::of`], but stop once `cancel` is cancelled
+fn count_loops_until(aba: &Aba, max_loops: Option<usize>, cancel: &CancellationToken) -> usize {
+    Loops::until(aba, max_loops, None, cancel).count()
+}
+
 fn __main() -> Result<(), Error> {
     // Init logger
     pretty_env_logger::init();
-    // Register SIGUSR1 handler
-    signal_hook::flag::register(signal_hook::consts::SIGUSR1, STOP_LOOP_COUNTING.clone())?;
+    // SIGUSR1 ends the loop search
+    let cancel = CancellationToken::new();
+    signal_hook::flag::register(signal_hook::consts::SIGUSR1, cancel.flag())?;
     let args = Args::parse();
     let content = std::fs::read_to_string(args.file).map_err(Error::OpeningAbaFile)?;
     let aba = parser::aba_file(&content)?;
-    println!("{}", count_loops(&aba, args.max_loops));
+    println!("{}", count_loops_until(&aba, args.max_loops, &cancel));
     Ok(())
 }
 
@@ -0,0 +1,91 @@
+//! Cancelling running computations
+//!
+//! A [`CancellationToken`] is handed to the computation, e.g. using
+//! [`Config::cancel`](crate::aba::problems::Config::cancel), and cancelled from another
+//! thread or a signal handler. Every computation has its own token, cancelling it
+//! does not affect other computations running in the same process.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Shared handle to cancel a computation
+///
+/// Clones share the same state. A token created with [`CancellationToken::linked`] is
+/// cancelled as soon as one of its parents is.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    parents: Arc<[CancellationToken]>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new token that is cancelled together with any of the `parents`
+    ///
+    /// Cancelling the new token does not cancel its parents.
+    pub fn linked<'a, I: IntoIterator<Item = &'a CancellationToken>>(parents: I) -> Self {
+        CancellationToken {
+            flag: Arc::default(),
+            parents: parents.into_iter().cloned().collect(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.parents.iter().any(Self::is_cancelled)
+    }
+
+    /// The flag set by [`CancellationToken::cancel`]
+    ///
+    /// Setting it cancels the token, which allows registering it with
+    /// [`signal_hook::flag::register`].
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+}
+
+/// Tokens are equal if they are clones of each other
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.flag, &other.flag)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+
+    #[test]
+    fn clones_share_the_state() {
+        let token = CancellationToken::new();
+        let other = CancellationToken::new();
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert!(!other.is_cancelled());
+        assert_eq!(token, token.clone());
+        assert_ne!(token, other);
+    }
+
+    #[test]
+    fn linked_tokens_follow_their_parents() {
+        let first = CancellationToken::new();
+        let second = CancellationToken::new();
+        let linked = CancellationToken::linked([&first, &second]);
+        assert!(!linked.is_cancelled());
+        second.cancel();
+        assert!(linked.is_cancelled());
+        // Cancelling the linked token leaves the parents alone
+        let linked = CancellationToken::linked([&first]);
+        linked.cancel();
+        assert!(!first.is_cancelled());
+    }
+}
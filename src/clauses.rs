@@ -1,8 +1,13 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::{Deref, DerefMut},
+};
+
+use bit_set::BitSet;
 
 use crate::literal::Literal;
 
-pub type ClauseList = Vec<Clause>;
 pub type RawClause = Vec<RawLiteral>;
 pub type RawLiteral = i32;
 
@@ -50,3 +55,239 @@ impl From<Vec<Literal>> for Clause {
         Clause { list }
     }
 }
+
+/// How much work [`simplify_clauses`] should do
+///
+/// Every level but [`ClauseSimplification::Off`] collects all clauses of a solver
+/// call in memory before the solver sees the first one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ClauseSimplification {
+    /// Hand the clauses to the solver as they are generated
+    #[default]
+    Off,
+    /// Remove duplicate literals, tautologies and duplicate clauses
+    Basic,
+    /// Additionally apply unit propagation and remove subsumed clauses
+    Full,
+}
+
+/// Counts collected by [`simplify_clauses`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClauseStatistics {
+    pub clauses_before: usize,
+    pub literals_before: usize,
+    pub clauses_after: usize,
+    pub literals_after: usize,
+    /// Clauses containing a literal and its negation
+    pub tautologies: usize,
+    /// Clauses that occurred more than once
+    pub duplicates: usize,
+    /// Variables fixed by unit propagation
+    pub units: usize,
+    /// Clauses satisfied by a fixed variable
+    pub satisfied: usize,
+    /// Clauses that are a superset of another clause
+    pub subsumed: usize,
+}
+
+/// Simplify a batch of clauses before they are added to the solver
+///
+/// Every literal is ordered by its variable, duplicate literals are removed and
+/// tautologies and duplicate clauses are dropped. With [`ClauseSimplification::Full`],
+/// unit propagation and subsumption are applied afterwards. Units found are kept as
+/// unit clauses, so the solver still knows the value of every variable.
+pub fn simplify_clauses<I: IntoIterator<Item = RawClause>>(
+    clauses: I,
+    level: ClauseSimplification,
+) -> (Vec<RawClause>, ClauseStatistics) {
+    let mut stats = ClauseStatistics::default();
+    let mut result: Vec<RawClause> = clauses
+        .into_iter()
+        .inspect(|clause| {
+            stats.clauses_before += 1;
+            stats.literals_before += clause.len();
+        })
+        .map(|mut clause| {
+            clause.sort_unstable_by_key(|lit| (lit.unsigned_abs(), *lit));
+            clause.dedup();
+            clause
+        })
+        .filter(|clause| {
+            // Sorted by variable, a literal and its negation are neighbours
+            let tautology = clause.windows(2).any(|pair| pair[0] == -pair[1]);
+            stats.tautologies += tautology as usize;
+            !tautology
+        })
+        .collect();
+    let len = result.len();
+    result.sort_unstable();
+    result.dedup();
+    stats.duplicates = len - result.len();
+    if level == ClauseSimplification::Full {
+        result = propagate_units(result, &mut stats);
+        result = remove_subsumed(result, &mut stats);
+    }
+    stats.clauses_after = result.len();
+    stats.literals_after = result.iter().map(Vec::len).sum();
+    (result, stats)
+}
+
+/// Fix all variables implied by unit clauses and simplify the other clauses with them
+fn propagate_units(clauses: Vec<RawClause>, stats: &mut ClauseStatistics) -> Vec<RawClause> {
+    let mut clauses: Vec<Option<RawClause>> = clauses.into_iter().map(Some).collect();
+    let mut occurrences: HashMap<RawLiteral, Vec<usize>> = HashMap::new();
+    for (idx, clause) in clauses.iter().enumerate() {
+        for lit in clause.iter().flatten() {
+            occurrences.entry(*lit).or_default().push(idx);
+        }
+    }
+    let mut queue: Vec<RawLiteral> = clauses
+        .iter()
+        .flatten()
+        .filter(|clause| clause.len() == 1)
+        .map(|clause| clause[0])
+        .collect();
+    let mut fixed: HashSet<RawLiteral> = HashSet::new();
+    let mut conflict = clauses.iter().flatten().any(Vec::is_empty);
+    while let Some(lit) = queue.pop() {
+        if fixed.contains(&-lit) {
+            conflict = true;
+            break;
+        }
+        if !fixed.insert(lit) {
+            continue;
+        }
+        // Clauses containing the literal are satisfied
+        for idx in occurrences.get(&lit).into_iter().flatten() {
+            if clauses[*idx].take().is_some_and(|clause| clause.len() > 1) {
+                stats.satisfied += 1;
+            }
+        }
+        // The negation can be removed from all other clauses
+        for idx in occurrences.get(&-lit).into_iter().flatten() {
+            if let Some(clause) = &mut clauses[*idx] {
+                clause.retain(|other| *other != -lit);
+                match clause.as_slice() {
+                    [] => conflict = true,
+                    [unit] => queue.push(*unit),
+                    _ => {}
+                }
+            }
+        }
+    }
+    stats.units = fixed.len();
+    if conflict {
+        // The empty clause is all that is left
+        return vec![vec![]];
+    }
+    let mut units: Vec<_> = fixed.into_iter().map(|lit| vec![lit]).collect();
+    units.sort_unstable();
+    units
+        .into_iter()
+        .chain(
+            clauses
+                .into_iter()
+                .flatten()
+                .filter(|clause| clause.len() > 1),
+        )
+        .collect()
+}
+
+/// Remove all clauses that are a superset of another clause
+fn remove_subsumed(clauses: Vec<RawClause>, stats: &mut ClauseStatistics) -> Vec<RawClause> {
+    let mut occurrences: HashMap<RawLiteral, Vec<usize>> = HashMap::new();
+    for (idx, clause) in clauses.iter().enumerate() {
+        for lit in clause {
+            occurrences.entry(*lit).or_default().push(idx);
+        }
+    }
+    let mut order: Vec<usize> = (0..clauses.len()).collect();
+    order.sort_by_key(|idx| clauses[*idx].len());
+    let mut removed = BitSet::with_capacity(clauses.len());
+    for idx in order {
+        if removed.contains(idx) {
+            continue;
+        }
+        let clause = &clauses[idx];
+        // Every superset contains the least frequent literal
+        let Some(candidates) = clause
+            .iter()
+            .map(|lit| &occurrences[lit])
+            .min_by_key(|candidates| candidates.len())
+        else {
+            continue;
+        };
+        for other in candidates {
+            if *other != idx
+                && !removed.contains(*other)
+                && clauses[*other].len() > clause.len()
+                && is_subset(clause, &clauses[*other])
+            {
+                removed.insert(*other);
+                stats.subsumed += 1;
+            }
+        }
+    }
+    clauses
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !removed.contains(*idx))
+        .map(|(_, clause)| clause)
+        .collect()
+}
+
+/// Whether all literals of `small` are in `large`, both ordered by variable
+fn is_subset(small: &[RawLiteral], large: &[RawLiteral]) -> bool {
+    // Both are sorted, every search continues where the last one ended
+    let mut large = large.iter();
+    small.iter().all(|lit| large.any(|other| other == lit))
+}
+
+impl Display for ClauseStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "clauses: {} -> {}, literals: {} -> {}, tautologies: {}, duplicates: {}, units: {}, satisfied: {}, subsumed: {}",
+            self.clauses_before,
+            self.clauses_after,
+            self.literals_before,
+            self.literals_after,
+            self.tautologies,
+            self.duplicates,
+            self.units,
+            self.satisfied,
+            self.subsumed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simplify_raw_clauses() {
+        let clauses = vec![
+            vec![1, -2, 1],
+            vec![-2, 1],
+            vec![3, -3, 4],
+            vec![5],
+            vec![-5, 6],
+            vec![-6, 2, 7],
+            vec![2, 7, 8],
+        ];
+        let (basic, stats) = simplify_clauses(clauses.clone(), ClauseSimplification::Basic);
+        assert_eq!(basic.len(), 5);
+        assert_eq!(stats.tautologies, 1);
+        assert_eq!(stats.duplicates, 1);
+        let (full, stats) = simplify_clauses(clauses, ClauseSimplification::Full);
+        assert_eq!(stats.units, 2);
+        assert_eq!(stats.subsumed, 1);
+        assert_eq!(full, vec![vec![5], vec![6], vec![1, -2], vec![2, 7]]);
+        let (unsat, _) = simplify_clauses(
+            vec![vec![1], vec![-1, 2], vec![-2]],
+            ClauseSimplification::Full,
+        );
+        assert_eq!(unsat, vec![Vec::<RawLiteral>::new()]);
+    }
+}
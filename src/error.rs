@@ -10,6 +10,20 @@ pub enum Error {
     OpeningAbaFile(::std::io::Error),
     #[error("sat call interrupted")]
     SatCallInterrupted,
+    #[error("sat solver failed: {_0}")]
+    Solver(std::io::Error),
+    #[error("unknown: time limit exceeded")]
+    Timeout,
+    #[error("unknown: conflict limit exceeded")]
+    ConflictLimit,
+    #[error("cancelled")]
+    Cancelled,
+    #[error("writing the proof: {_0}")]
+    Proof(std::io::Error),
+    #[error("certification failed: {_0}")]
+    Certification(String),
+    #[error("the encoding needs {_0} variables, more than the solver supports")]
+    TooManyVariables(u64),
     #[error("problem internal check failed: {_0}")]
     ProblemCheckFailed(String),
     #[error("formatting: {_0}")]
@@ -18,6 +32,13 @@ pub enum Error {
     Output(#[from] std::io::Error),
 }
 
+impl Error {
+    /// The answer is unknown, because a time or conflict limit was hit
+    pub fn is_limit(&self) -> bool {
+        matches!(self, Error::Timeout | Error::ConflictLimit)
+    }
+}
+
 impl From<nom::Err<nom::error::Error<&'_ str>>> for Error {
     fn from(value: nom::Err<nom::error::Error<&'_ str>>) -> Self {
         Error::from(value.map_input(|input| input.to_owned()))
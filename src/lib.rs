@@ -9,20 +9,17 @@ macro_rules! set {
     }}
 }
 
-lazy_static::lazy_static! {
-    pub static ref STOP_LOOP_COUNTING: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-}
-
 pub mod aba;
+pub mod cancel;
 pub mod clauses;
 pub mod error;
 pub mod literal;
 pub mod mapper;
 pub mod parser;
+pub mod sat;
 
 #[cfg(test)]
 mod tests;
 
-use std::sync::{atomic::AtomicBool, Arc};
-
+pub use cancel::CancellationToken;
 pub use error::{Error, Result};
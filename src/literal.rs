@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use crate::aba::Context;
+
 pub mod lits {
     use crate::aba::Num;
 
@@ -40,29 +42,57 @@ pub mod lits {
         };
     }
 
+    /// Atom of the theory copy with the given index, see [`Context`](crate::aba::Context)
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct Candidate(Num);
-    into_raw!(Candidate from Num);
+    pub struct TheoryAtom(pub(crate) usize, pub(crate) Num);
+    into_raw!(TheoryAtom);
 
+    /// Whether the body of a rule is derived in the theory copy with the given index
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct CandidateRuleBodyActive(usize);
-    into_raw!(CandidateRuleBodyActive from usize);
+    pub struct RuleBodyActive(pub(crate) usize, pub(crate) usize);
+    into_raw!(RuleBodyActive);
 
+    /// Loop helper of the theory copy with the given index
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct Attacker(Num);
-    into_raw!(Attacker from Num);
+    pub struct LoopHelper(pub(crate) usize, pub(crate) usize);
+    into_raw!(LoopHelper);
 
+    /// Atom of the [`Context::CANDIDATE`](crate::aba::Context::CANDIDATE) theory
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct AttackerRuleBodyActive(usize);
-    into_raw!(AttackerRuleBodyActive from usize);
+    pub struct Candidate(pub(crate) Num);
+
+    impl From<Num> for Candidate {
+        fn from(value: Num) -> Self {
+            Self(value)
+        }
+    }
+
+    impl From<Candidate> for crate::literal::RawLiteral {
+        fn from(Candidate(atom): Candidate) -> Self {
+            crate::aba::Context::CANDIDATE.atom(atom).into()
+        }
+    }
 
+    /// Atom of the [`Context::ATTACKER`](crate::aba::Context::ATTACKER) theory
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct CandidateLoopHelper(usize);
-    into_raw!(CandidateLoopHelper from usize);
+    pub struct Attacker(pub(crate) Num);
+
+    impl From<Num> for Attacker {
+        fn from(value: Num) -> Self {
+            Self(value)
+        }
+    }
+
+    impl From<Attacker> for crate::literal::RawLiteral {
+        fn from(Attacker(atom): Attacker) -> Self {
+            crate::aba::Context::ATTACKER.atom(atom).into()
+        }
+    }
 
+    /// Auxiliary variable of the symmetry breaking clauses
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    pub struct AttackerLoopHelper(usize);
-    into_raw!(AttackerLoopHelper from usize);
+    pub struct SymmetryHelper(pub(crate) usize);
+    into_raw!(SymmetryHelper from usize);
 }
 
 /// A Literal can be used in SAT [`Clause`](crate::clauses::Clause)s
@@ -78,12 +108,41 @@ pub enum Literal {
 /// extend this type for every new literal type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RawLiteral {
-    Candidate(lits::Candidate),
-    CandidateRuleBodyActive(lits::CandidateRuleBodyActive),
-    CandidateLoopHelper(lits::CandidateLoopHelper),
-    Attacker(lits::Attacker),
-    AttackerRuleBodyActive(lits::AttackerRuleBodyActive),
-    AttackerLoopHelper(lits::AttackerLoopHelper),
+    TheoryAtom(lits::TheoryAtom),
+    RuleBodyActive(lits::RuleBodyActive),
+    LoopHelper(lits::LoopHelper),
+    SymmetryHelper(lits::SymmetryHelper),
+}
+
+/// Readable name of the literal, atoms of the first two contexts are shown as
+/// `Candidate(atom)` and `Attacker(atom)`
+impl std::fmt::Display for RawLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RawLiteral::TheoryAtom(lits::TheoryAtom(context, atom))
+                if context == Context::CANDIDATE.0 =>
+            {
+                write!(f, "Candidate({atom})")
+            }
+            RawLiteral::TheoryAtom(lits::TheoryAtom(context, atom))
+                if context == Context::ATTACKER.0 =>
+            {
+                write!(f, "Attacker({atom})")
+            }
+            RawLiteral::TheoryAtom(lits::TheoryAtom(context, atom)) => {
+                write!(f, "TheoryAtom({context}, {atom})")
+            }
+            RawLiteral::RuleBodyActive(lits::RuleBodyActive(context, rule)) => {
+                write!(f, "RuleBodyActive({context}, {rule})")
+            }
+            RawLiteral::LoopHelper(lits::LoopHelper(context, r#loop)) => {
+                write!(f, "LoopHelper({context}, {loop})")
+            }
+            RawLiteral::SymmetryHelper(lits::SymmetryHelper(helper)) => {
+                write!(f, "SymmetryHelper({helper})")
+            }
+        }
+    }
 }
 
 /// Convert the type into it's literal
@@ -1,22 +1,26 @@
-#![feature(iter_collect_into)]
-#![feature(iter_intersperse)]
-#![feature(doc_notable_trait)]
-
-use std::{collections::HashSet, fmt::Write as WriteFmt, fs::read_to_string, io::Write as WriteIo};
+use std::{
+    collections::HashSet, fmt::Write as WriteFmt, fs::read_to_string, io::Write as WriteIo,
+    process::ExitCode,
+};
 
 use aba2sat::{
     aba::{
         self,
+        check::{self, Violation},
         problems::{
+            acceptance::Acceptance,
             admissibility::{
                 DecideCredulousAdmissibility, EnumerateAdmissibleExtensions,
                 SampleAdmissibleExtension, VerifyAdmissibleExtension,
             },
             complete::{DecideCredulousComplete, EnumerateCompleteExtensions},
+            encode::{encode, encode_multishot},
+            report::Semantics,
+            Config, Outcome,
         },
-        Num,
+        Aba, Num,
     },
-    Error, STOP_LOOP_COUNTING,
+    CancellationToken, Error,
 };
 use aba2sat::{parser, Result};
 use args::ARGS;
@@ -24,6 +28,9 @@ use clap::Parser;
 
 mod args;
 
+/// Exit status if the answer is unknown because a limit was hit, like `timeout(1)`
+const LIMIT_EXIT_STATUS: u8 = 124;
+
 trait IccmaFormattable {
     fn fmt_iccma(&self) -> Result<String>;
 }
@@ -31,8 +38,15 @@ trait IccmaFormattable {
 fn __main() -> Result {
     // Init logger
     pretty_env_logger::init();
-    // Register SIGUSR1 handler
-    signal_hook::flag::register(signal_hook::consts::SIGUSR1, STOP_LOOP_COUNTING.clone())?;
+    // SIGUSR1 ends the loop search, SIGINT and SIGTERM cancel the problem.
+    // Signalling twice terminates immediately
+    let stop_loops = CancellationToken::new();
+    let cancel = CancellationToken::new();
+    signal_hook::flag::register(signal_hook::consts::SIGUSR1, stop_loops.flag())?;
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        signal_hook::flag::register_conditional_shutdown(signal, 1, cancel.flag())?;
+        signal_hook::flag::register(signal, cancel.flag())?;
+    }
 
     let args = match &*ARGS {
         Some(args) => args,
@@ -43,43 +57,177 @@ fn __main() -> Result {
     };
     let content = read_to_string(&args.file).map_err(Error::OpeningAbaFile)?;
     let aba = parser::aba_file(&content)?;
+    // The framework as given, the problems prepare and change their own copy
+    let original = args.certify.then(|| aba.clone());
+    let certify = |check: Check, extensions: &[HashSet<Num>]| match &original {
+        Some(aba) => certify_extensions(aba, check, extensions),
+        None => Ok(()),
+    };
+    let config = Config {
+        max_loops: args.max_loops,
+        grounded: args.grounded,
+        simplify: !args.no_simplify,
+        // Answers found by a shortcut have no proof
+        shortcuts: !args.no_shortcuts && args.proof.is_none(),
+        clauses: args.clauses,
+        symmetries: args.symmetries,
+        backend: args.backend(),
+        limits: args.limits(),
+        cancel,
+        stop_loops,
+        proof: args.proof.clone(),
+    };
     let result = match &args.problem {
-        args::Problems::VerifyAdmissibility { set } => aba::problems::solve(
-            VerifyAdmissibleExtension {
-                assumptions: set.iter().cloned().collect(),
-            },
-            aba,
-            args.max_loops,
-        )?
-        .fmt_iccma(),
-        args::Problems::EnumerateAdmissibility => aba::problems::multishot_solve(
-            EnumerateAdmissibleExtensions::default(),
-            aba,
-            args.max_loops,
-        )?
-        .fmt_iccma(),
-        args::Problems::SampleAdmissibility => {
-            aba::problems::solve(SampleAdmissibleExtension, aba, args.max_loops)?.fmt_iccma()
+        args::Problems::Encodable(args::EncodableProblem::VerifyAdmissibility { set }) => {
+            let assumptions: HashSet<_> = set.iter().cloned().collect();
+            let result = aba::problems::solve(
+                VerifyAdmissibleExtension {
+                    assumptions: assumptions.clone(),
+                },
+                aba,
+                &config,
+            )?;
+            if let Some(aba) = &original {
+                certify_verification(aba, check::admissible, &assumptions, result)?;
+            }
+            result.fmt_iccma()
+        }
+        args::Problems::Encodable(args::EncodableProblem::EnumerateAdmissibility) => {
+            let outcome = aba::problems::multishot_solve_partial(
+                EnumerateAdmissibleExtensions::default(),
+                aba,
+                &config,
+            )?;
+            let (Outcome::Complete(found) | Outcome::Partial(found, _)) = &outcome;
+            certify(check::admissible, found)?;
+            partial(outcome)
         }
-        args::Problems::DecideCredulousAdmissibility { query } => aba::problems::solve(
+        args::Problems::Encodable(args::EncodableProblem::SampleAdmissibility) => {
+            let extension = aba::problems::solve(SampleAdmissibleExtension, aba, &config)?;
+            certify(check::admissible, std::slice::from_ref(&extension))?;
+            extension.fmt_iccma()
+        }
+        args::Problems::Encodable(args::EncodableProblem::DecideCredulousAdmissibility {
+            query,
+        }) => aba::problems::solve(
             DecideCredulousAdmissibility { element: *query },
             aba,
-            args.max_loops,
-        )?
-        .fmt_iccma(),
-        args::Problems::EnumerateComplete => aba::problems::multishot_solve(
-            EnumerateCompleteExtensions::default(),
-            aba,
-            args.max_loops,
-        )?
-        .fmt_iccma(),
-        args::Problems::DecideCredulousComplete { query } => aba::problems::solve(
-            DecideCredulousComplete { element: *query },
-            aba,
-            args.max_loops,
+            &config,
         )?
         .fmt_iccma(),
+        args::Problems::Encodable(args::EncodableProblem::EnumerateComplete) if args.split => {
+            let extensions = aba::problems::split::enumerate_complete(aba, &config)?;
+            certify(check::complete, &extensions)?;
+            extensions.fmt_iccma()
+        }
+        args::Problems::Encodable(args::EncodableProblem::EnumerateComplete)
+            if args.threads > 1 =>
+        {
+            let outcome = aba::problems::parallel::enumerate_complete(aba, &config, args.threads)?;
+            let (Outcome::Complete(found) | Outcome::Partial(found, _)) = &outcome;
+            certify(check::complete, found)?;
+            partial(outcome)
+        }
+        args::Problems::Encodable(args::EncodableProblem::EnumerateComplete) => {
+            let outcome = aba::problems::multishot_solve_partial(
+                EnumerateCompleteExtensions::default(),
+                aba,
+                &config,
+            )?;
+            let (Outcome::Complete(found) | Outcome::Partial(found, _)) = &outcome;
+            certify(check::complete, found)?;
+            partial(outcome)
+        }
+        // Certifying needs the extensions, not just the product of the counts
+        args::Problems::CountComplete if args.split && args.certify => {
+            let extensions = aba::problems::split::enumerate_complete(aba, &config)?;
+            certify(check::complete, &extensions)?;
+            (extensions.len() as u128).fmt_iccma()
+        }
+        args::Problems::CountComplete if args.split => {
+            aba::problems::split::count_complete(aba, &config)?.fmt_iccma()
+        }
+        args::Problems::CountComplete if args.threads > 1 => {
+            let extensions =
+                aba::problems::parallel::enumerate_complete(aba, &config, args.threads)?
+                    .complete()?;
+            certify(check::complete, &extensions)?;
+            (extensions.len() as u128).fmt_iccma()
+        }
+        args::Problems::CountComplete => {
+            let extensions = aba::problems::multishot_solve(
+                EnumerateCompleteExtensions::default(),
+                aba,
+                &config,
+            )?;
+            certify(check::complete, &extensions)?;
+            (extensions.len() as u128).fmt_iccma()
+        }
+        args::Problems::Encodable(args::EncodableProblem::DecideCredulousComplete { query }) => {
+            aba::problems::solve(DecideCredulousComplete { element: *query }, aba, &config)?
+                .fmt_iccma()
+        }
+        // The credulous answers of the complete semantics are witnessed by admissible sets
+        args::Problems::AcceptanceComplete => {
+            let acceptance = aba::problems::acceptance::complete_acceptance(aba, &config)?;
+            certify(check::admissible, &acceptance.witnesses)?;
+            acceptance.fmt_iccma()
+        }
+        args::Problems::AcceptanceStable => {
+            let acceptance = aba::problems::acceptance::stable_acceptance(aba, &config)?;
+            certify(check::stable, &acceptance.witnesses)?;
+            acceptance.fmt_iccma()
+        }
+        // Preferred extensions are complete, their maximality is not checked
+        args::Problems::AcceptancePreferred => {
+            let acceptance = aba::problems::acceptance::preferred_acceptance(aba, &config)?;
+            certify(check::complete, &acceptance.witnesses)?;
+            acceptance.fmt_iccma()
+        }
+        args::Problems::Report { format } => {
+            let report = aba::problems::report::report(aba, &config)?;
+            for (semantics, acceptance) in &report.semantics {
+                certify(witness_check(*semantics), &acceptance.witnesses)?;
+            }
+            match format {
+                args::ReportFormat::Text => report.to_table(),
+                args::ReportFormat::Json => report.to_json(),
+            }
+        }
+        args::Problems::Encode { problem } => {
+            let encoding = match problem {
+                args::EncodableProblem::VerifyAdmissibility { set } => encode(
+                    &VerifyAdmissibleExtension {
+                        assumptions: set.iter().cloned().collect(),
+                    },
+                    aba,
+                    &config,
+                ),
+                args::EncodableProblem::DecideCredulousAdmissibility { query } => encode(
+                    &DecideCredulousAdmissibility { element: *query },
+                    aba,
+                    &config,
+                ),
+                args::EncodableProblem::EnumerateAdmissibility => {
+                    encode_multishot(&EnumerateAdmissibleExtensions::default(), aba, &config)
+                }
+                args::EncodableProblem::SampleAdmissibility => {
+                    encode(&SampleAdmissibleExtension, aba, &config)
+                }
+                args::EncodableProblem::EnumerateComplete => {
+                    encode_multishot(&EnumerateCompleteExtensions::default(), aba, &config)
+                }
+                args::EncodableProblem::DecideCredulousComplete { query } => {
+                    encode(&DecideCredulousComplete { element: *query }, aba, &config)
+                }
+            }?;
+            encoding.to_dimacs()
+        }
     }?;
+    print(&result)
+}
+
+fn print(result: &str) -> Result {
     let mut stdout = std::io::stdout().lock();
     match writeln!(stdout, "{}", result) {
         Ok(()) => Ok(()),
@@ -90,8 +238,75 @@ fn __main() -> Result {
     }
 }
 
-fn main() -> Result {
-    __main().inspect_err(|why| eprintln!("Error: {why}"))
+/// Checks an extension of one semantics, see [`aba::check`]
+type Check = fn(&Aba, &HashSet<Num>) -> std::result::Result<(), Violation>;
+
+/// The check passed by the witnesses of the acceptance under `semantics`
+fn witness_check(semantics: Semantics) -> Check {
+    match semantics {
+        Semantics::ConflictFree => check::conflict_free,
+        Semantics::Admissible | Semantics::Complete => check::admissible,
+        Semantics::Grounded | Semantics::Preferred => check::complete,
+        Semantics::Stable => check::stable,
+    }
+}
+
+/// Fail if one of the `extensions` does not pass `check`
+fn certify_extensions(aba: &Aba, check: Check, extensions: &[HashSet<Num>]) -> Result {
+    for extension in extensions {
+        if let Err(why) = check(aba, extension) {
+            return Err(Error::Certification(format!(
+                "{}: {why}",
+                extension.fmt_iccma()?
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Fail if the answer to the verification of `assumptions` disagrees with `check`
+fn certify_verification(
+    aba: &Aba,
+    check: Check,
+    assumptions: &HashSet<Num>,
+    answer: bool,
+) -> Result {
+    match (check(aba, assumptions), answer) {
+        (Ok(()), true) | (Err(_), false) => Ok(()),
+        (Ok(()), false) => Err(Error::Certification(format!(
+            "{} was rejected, but passes the check",
+            assumptions.fmt_iccma()?
+        ))),
+        (Err(why), true) => Err(Error::Certification(format!(
+            "{} was accepted, but {why}",
+            assumptions.fmt_iccma()?
+        ))),
+    }
+}
+
+/// Print the solutions found before the solver was interrupted, then fail
+fn partial<T: IccmaFormattable>(outcome: Outcome<T>) -> Result<String> {
+    match outcome {
+        Outcome::Complete(output) => output.fmt_iccma(),
+        Outcome::Partial(output, why) => {
+            print(&output.fmt_iccma()?)?;
+            Err(why)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match __main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(why) => {
+            eprintln!("Error: {why}");
+            if why.is_limit() {
+                ExitCode::from(LIMIT_EXIT_STATUS)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
 }
 
 impl IccmaFormattable for Vec<HashSet<Num>> {
@@ -120,6 +335,22 @@ impl IccmaFormattable for HashSet<Num> {
     }
 }
 
+impl IccmaFormattable for Acceptance {
+    fn fmt_iccma(&self) -> Result<String> {
+        Ok(format!(
+            "{}\n{}",
+            self.credulous.fmt_iccma()?,
+            self.skeptical.fmt_iccma()?
+        ))
+    }
+}
+
+impl IccmaFormattable for u128 {
+    fn fmt_iccma(&self) -> Result<String> {
+        Ok(self.to_string())
+    }
+}
+
 impl IccmaFormattable for bool {
     fn fmt_iccma(&self) -> Result<String> {
         let output = match self {
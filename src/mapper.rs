@@ -1,24 +1,86 @@
-use std::collections::HashMap;
-
 use crate::{
+    aba::{Num, PreparedAba},
     clauses::{Clause, RawClause},
-    literal::{Literal, RawLiteral},
+    error::{Error, Result},
+    literal::{
+        lits::{LoopHelper, RuleBodyActive, SymmetryHelper, TheoryAtom},
+        Literal, RawLiteral,
+    },
+    sat::Backend,
 };
 
-#[derive(Debug, Default)]
+/// Translation between [`Literal`]s and the variables of the SAT solver
+///
+/// Every context (see [`Context`](crate::aba::Context)) owns a contiguous block of
+/// variables. A block contains one variable per atom, followed by one per rule and
+/// one per loop. Variables are computed from the index of the literal and the offset
+/// of its block, no lookup is necessary. Helpers that do not belong to a context
+/// follow after all blocks.
+///
+/// ```text
+/// | Candidate                     | Attacker                      | Context(2) ... |                  |
+/// | atoms | rule helper | loops   | atoms | rule helper | loops   |                | symmetry helper  |
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Mapper {
-    map: HashMap<RawLiteral, u32>,
+    /// Number of atom variables per block, atoms are used as their own index
+    atoms: u32,
+    /// Number of rule helpers per block
+    rules: u32,
+    /// Number of loop helpers per block
+    loops: u32,
+    /// Number of blocks, one per context
+    contexts: u32,
+    /// Number of symmetry helpers after all blocks
+    symmetry_helpers: u32,
 }
 
 impl Mapper {
-    pub fn new() -> Self {
+    pub fn new(aba: &PreparedAba) -> Self {
+        let atoms = aba
+            .index()
+            .atoms()
+            .last()
+            .map(|max| max + 1)
+            .unwrap_or_default();
         Mapper {
-            map: HashMap::new(),
+            atoms,
+            rules: aba.rules.len() as u32,
+            loops: aba.loop_count() as u32,
+            contexts: crate::aba::problems::DEFAULT_CONTEXTS as u32,
+            symmetry_helpers: 0,
+        }
+    }
+
+    /// Reserve blocks for `count` contexts, the default are the two for
+    /// [`Context::CANDIDATE`](crate::aba::Context::CANDIDATE) and
+    /// [`Context::ATTACKER`](crate::aba::Context::ATTACKER)
+    pub fn with_contexts(mut self, count: usize) -> Self {
+        self.contexts = count as u32;
+        self
+    }
+
+    /// Reserve `count` variables for [`SymmetryHelper`]s
+    pub fn with_symmetry_helpers(mut self, count: usize) -> Self {
+        self.symmetry_helpers = count as u32;
+        self
+    }
+
+    /// Fails if the variables do not fit into the `i32` literals of the solver
+    ///
+    /// Overlapping variables would silently produce wrong answers, every mapper
+    /// must be checked before it translates literals.
+    pub fn checked(self) -> Result<Self> {
+        let block = self.atoms as u64 + self.rules as u64 + self.loops as u64;
+        let variables = (block * self.contexts as u64).saturating_add(self.symmetry_helpers as u64);
+        if variables > i32::MAX as u64 {
+            return Err(Error::TooManyVariables(variables));
         }
+        Ok(self)
     }
 
-    pub fn as_raw_iter<'s, I: IntoIterator<Item = &'s Clause> + 's>(
-        &'s mut self,
+    pub fn as_raw_iter<'s, I: IntoIterator<Item = Clause> + 's>(
+        &'s self,
         aba_clauses: I,
     ) -> impl Iterator<Item = RawClause> + 's {
         aba_clauses
@@ -26,33 +88,164 @@ impl Mapper {
             .map(|clause| clause.iter().map(|lit| self.as_raw(lit)).collect())
     }
 
-    pub fn as_raw(&mut self, lit: &Literal) -> i32 {
-        let key = self.map.get(lit).copied().unwrap_or_else(|| {
-            debug_assert!(self.map.len() <= i32::MAX as usize, "Mapper overflowed");
-            let new = self.map.len() as u32 + 1;
-            self.map.insert(**lit, new);
-            new
-        }) as i32;
+    pub fn as_raw(&self, lit: &Literal) -> i32 {
+        let key = self.variable(lit) as i32;
         match lit {
             Literal::Pos(_) => key,
             Literal::Neg(_) => -key,
         }
     }
 
+    /// The largest variable that may be used by this mapper
+    pub fn max_variable(&self) -> i32 {
+        (self.block() * self.contexts + self.symmetry_helpers) as i32
+    }
+
+    /// Translate a variable of the SAT solver back into its literal
+    pub fn literal(&self, variable: u32) -> Option<RawLiteral> {
+        if variable == 0 || variable > self.max_variable() as u32 {
+            return None;
+        }
+        if variable > self.block() * self.contexts {
+            let helper = (variable - self.block() * self.contexts - 1) as usize;
+            return Some(SymmetryHelper(helper).into());
+        }
+        let context = ((variable - 1) / self.block()) as usize;
+        let offset = (variable - 1) % self.block();
+        let lit = if offset < self.atoms {
+            TheoryAtom(context, offset as Num).into()
+        } else if offset < self.atoms + self.rules {
+            RuleBodyActive(context, (offset - self.atoms) as usize).into()
+        } else {
+            LoopHelper(context, (offset - self.atoms - self.rules) as usize).into()
+        };
+        Some(lit)
+    }
+
+    /// All variables of this mapper together with their literal
+    pub fn variables(&self) -> impl Iterator<Item = (u32, RawLiteral)> + '_ {
+        (1..=self.max_variable() as u32).map(|variable| {
+            (
+                variable,
+                self.literal(variable).expect("variable is mapped"),
+            )
+        })
+    }
+
     #[cfg(debug_assertions)]
-    pub fn reconstruct<'s>(
-        &'s self,
-        sat: &'s cadical::Solver,
-    ) -> impl Iterator<Item = Literal> + 's {
-        self.map.iter().flat_map(|(lit, raw)| {
-            sat.value(*raw as i32).map(|result| match result {
-                true => Literal::Pos(*lit),
-                false => Literal::Neg(*lit),
+    pub fn reconstruct<'s>(&'s self, sat: &'s dyn Backend) -> impl Iterator<Item = Literal> + 's {
+        (1..=sat.max_variable()).flat_map(|raw| {
+            let lit = self.literal(raw as u32)?;
+            sat.value(raw).map(|result| match result {
+                true => Literal::Pos(lit),
+                false => Literal::Neg(lit),
             })
         })
     }
 
     pub fn get_raw(&self, lit: &Literal) -> Option<i32> {
-        self.map.get(lit).map(|&raw| raw as i32)
+        let in_range = match **lit {
+            RawLiteral::TheoryAtom(TheoryAtom(context, atom)) => {
+                context < self.contexts as usize && atom < self.atoms
+            }
+            RawLiteral::RuleBodyActive(RuleBodyActive(context, rule)) => {
+                context < self.contexts as usize && rule < self.rules as usize
+            }
+            RawLiteral::LoopHelper(LoopHelper(context, r#loop)) => {
+                context < self.contexts as usize && r#loop < self.loops as usize
+            }
+            RawLiteral::SymmetryHelper(SymmetryHelper(helper)) => {
+                helper < self.symmetry_helpers as usize
+            }
+        };
+        in_range.then(|| self.as_raw(lit))
+    }
+
+    fn block(&self) -> u32 {
+        self.atoms + self.rules + self.loops
+    }
+
+    fn variable(&self, lit: &RawLiteral) -> u32 {
+        if let RawLiteral::SymmetryHelper(SymmetryHelper(helper)) = *lit {
+            debug_assert!(
+                helper < self.symmetry_helpers as usize,
+                "Literal {lit:?} is outside of the mapped range"
+            );
+            return self.block() * self.contexts + helper as u32 + 1;
+        }
+        let (context, offset) = match *lit {
+            RawLiteral::TheoryAtom(TheoryAtom(context, atom)) => (context, atom),
+            RawLiteral::RuleBodyActive(RuleBodyActive(context, rule)) => {
+                (context, self.atoms + rule as u32)
+            }
+            RawLiteral::LoopHelper(LoopHelper(context, r#loop)) => {
+                (context, self.atoms + self.rules + r#loop as u32)
+            }
+            RawLiteral::SymmetryHelper(_) => unreachable!("Handled above"),
+        };
+        debug_assert!(
+            offset < self.block() && context < self.contexts as usize,
+            "Literal {lit:?} is outside of the mapped range"
+        );
+        context as u32 * self.block() + offset + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        aba::debug::DebugAba,
+        aba::Context,
+        cancel::CancellationToken,
+        literal::{
+            lits::{Attacker, Candidate},
+            IntoLiteral,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn variables_are_dense_and_reversible() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'b')
+            .with_rule('b', ['a'])
+            .with_rule('b', ['c'])
+            .with_rule('c', ['b'])
+            .aba()
+            .clone()
+            .prepare_until(None, None, &CancellationToken::new(), false);
+        let map = Mapper::new(&aba).with_contexts(3).checked().unwrap();
+        let lits = [
+            Candidate(1).pos(),
+            Candidate(3).pos(),
+            Context::CANDIDATE.rule(2).pos(),
+            Attacker(2).neg(),
+            Context::ATTACKER.r#loop(0).pos(),
+            Context(2).atom(1).pos(),
+            Context(2).rule(2).neg(),
+            Context(2).r#loop(0).pos(),
+        ];
+        let mut seen = std::collections::HashSet::new();
+        for lit in lits {
+            let raw = map.as_raw(&lit);
+            assert!(raw.unsigned_abs() as i32 <= map.max_variable());
+            assert!(seen.insert(raw.abs()), "{lit:?} shares a variable");
+            assert_eq!(map.literal(raw.unsigned_abs()), Some(*lit));
+            assert_eq!(raw < 0, matches!(lit, Literal::Neg(_)));
+        }
+        assert_eq!(map.literal(0), None);
+        assert_eq!(map.literal(map.max_variable() as u32 + 1), None);
+    }
+
+    #[test]
+    fn overflowing_variables_are_rejected() {
+        let aba = DebugAba::default()
+            .with_assumption('a', 'b')
+            .aba()
+            .clone()
+            .prepare(None);
+        let map = Mapper::new(&aba).with_contexts(i32::MAX as usize).checked();
+        assert!(matches!(map, Err(Error::TooManyVariables(_))));
     }
 }
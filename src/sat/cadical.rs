@@ -0,0 +1,346 @@
+//! [`Backend`] using the [CaDiCaL](https://github.com/arminbiere/cadical) solver
+//!
+//! The `cadical` crate builds and links the solver, but its bindings neither set
+//! options nor trace proofs. The solver is used through the C API of CaDiCaL
+//! instead, `build.rs` adds the functions this API lacks.
+use std::{
+    ffi::CString,
+    io,
+    os::raw::{c_char, c_int, c_void},
+    path::Path,
+};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+};
+
+use super::Backend;
+
+// Make sure the solver library of the crate is linked
+extern crate cadical;
+
+// Declared in `ccadical.h` of CaDiCaL
+extern "C" {
+    fn ccadical_init() -> *mut c_void;
+    fn ccadical_release(ptr: *mut c_void);
+    fn ccadical_add(ptr: *mut c_void, lit: c_int);
+    fn ccadical_assume(ptr: *mut c_void, lit: c_int);
+    fn ccadical_solve(ptr: *mut c_void) -> c_int;
+    fn ccadical_val(ptr: *mut c_void, lit: c_int) -> c_int;
+    fn ccadical_set_terminate(
+        ptr: *mut c_void,
+        state: *mut c_void,
+        terminate: Option<extern "C" fn(*mut c_void) -> c_int>,
+    );
+    fn ccadical_set_option(ptr: *mut c_void, name: *const c_char, val: c_int);
+    fn ccadical_get_option(ptr: *mut c_void, name: *const c_char) -> c_int;
+    fn ccadical_limit(ptr: *mut c_void, name: *const c_char, limit: c_int);
+}
+
+// Compiled by `build.rs`
+extern "C" {
+    fn aba2sat_cadical_configure(ptr: *mut c_void, name: *const c_char) -> c_int;
+    fn aba2sat_cadical_trace_proof(ptr: *mut c_void, path: *const c_char) -> c_int;
+    fn aba2sat_cadical_flush_proof_trace(ptr: *mut c_void);
+}
+
+/// Predefined configurations of CaDiCaL
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    #[default]
+    Default,
+    /// Disable all preprocessing
+    Plain,
+    /// Target satisfiable instances
+    Sat,
+    /// Target unsatisfiable instances
+    Unsat,
+}
+
+/// Options of a CaDiCaL instance, `None` keeps the default of the solver
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CadicalOptions {
+    /// Seed of the random number generator
+    pub seed: Option<u32>,
+    pub preset: Option<Preset>,
+    /// Initial phase of all variables
+    pub phase: Option<bool>,
+}
+
+impl CadicalOptions {
+    /// Options for the worker `idx` of a portfolio, the first worker uses `self`
+    pub fn diversified(&self, idx: usize) -> Self {
+        if idx == 0 {
+            return self.clone();
+        }
+        const PRESETS: [Preset; 4] = [Preset::Default, Preset::Sat, Preset::Unsat, Preset::Plain];
+        CadicalOptions {
+            seed: Some(self.seed.unwrap_or_default().wrapping_add(idx as u32)),
+            preset: Some(PRESETS[idx / 2 % PRESETS.len()]),
+            phase: Some(idx % 2 == 0),
+        }
+    }
+}
+
+pub struct Cadical {
+    ptr: *mut c_void,
+    /// Token polled by the solver, boxed so its address stays valid while the
+    /// solver uses it
+    terminate: Option<Box<CancellationToken>>,
+    /// Maximum number of conflicts per solver call
+    conflict_limit: Option<u32>,
+    /// Largest variable added to the solver
+    max_variable: i32,
+    /// No clause was added and no call was made, options can still be changed
+    configuring: bool,
+    /// Whether a proof is traced, which has to be flushed after every call
+    tracing: bool,
+}
+
+/// Like the `Solver` of the `cadical` crate, the solver is only used through
+/// `&mut self` and keeps no thread local state
+unsafe impl Send for Cadical {}
+
+impl Cadical {
+    pub fn new(options: &CadicalOptions) -> Self {
+        let mut solver = Cadical {
+            ptr: unsafe { ccadical_init() },
+            terminate: None,
+            conflict_limit: None,
+            max_variable: 0,
+            configuring: true,
+            tracing: false,
+        };
+        // Presets must be applied before any other option
+        if let Some(preset) = options.preset {
+            let name = match preset {
+                Preset::Default => "default",
+                Preset::Plain => "plain",
+                Preset::Sat => "sat",
+                Preset::Unsat => "unsat",
+            };
+            let name = CString::new(name).expect("preset names contain no null bytes");
+            let known = unsafe { aba2sat_cadical_configure(solver.ptr, name.as_ptr()) };
+            assert!(known != 0, "CaDiCaL knows all presets");
+        }
+        if let Some(seed) = options.seed {
+            solver
+                .set_option("seed", seed.min(i32::MAX as u32) as i32)
+                .expect("CaDiCaL has a seed option");
+        }
+        if let Some(phase) = options.phase {
+            solver
+                .set_option("phase", phase as i32)
+                .expect("CaDiCaL has a phase option");
+        }
+        solver
+    }
+
+    /// Set an option of the solver, only possible before the first clause
+    fn set_option(&mut self, name: &str, value: i32) -> Result<(), String> {
+        if !self.configuring {
+            return Err(format!("cannot set {name} after adding clauses"));
+        }
+        let name = CString::new(name).map_err(|why| why.to_string())?;
+        // Unknown options are ignored and values outside their range are clamped
+        unsafe { ccadical_set_option(self.ptr, name.as_ptr(), value) };
+        match unsafe { ccadical_get_option(self.ptr, name.as_ptr()) } {
+            actual if actual == value => Ok(()),
+            _ => Err(format!("invalid option {name:?} = {value}")),
+        }
+    }
+
+    extern "C" fn terminate_callback(state: *mut c_void) -> c_int {
+        let token = unsafe { &*(state as *const CancellationToken) };
+        token.is_cancelled() as c_int
+    }
+
+    fn requested(&self) -> bool {
+        self.terminate
+            .as_deref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+impl Drop for Cadical {
+    fn drop(&mut self) {
+        unsafe { ccadical_release(self.ptr) };
+    }
+}
+
+impl Default for Cadical {
+    fn default() -> Self {
+        Cadical::new(&CadicalOptions::default())
+    }
+}
+
+impl Backend for Cadical {
+    fn add_clause(&mut self, clause: RawClause) {
+        self.configuring = false;
+        for lit in clause {
+            debug_assert!(lit != 0 && lit != i32::MIN);
+            self.max_variable = self.max_variable.max(lit.abs());
+            unsafe { ccadical_add(self.ptr, lit) };
+        }
+        unsafe { ccadical_add(self.ptr, 0) };
+    }
+
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool> {
+        self.configuring = false;
+        // Easy formulas are solved without ever asking the callback
+        if self.requested() {
+            return None;
+        }
+        // Limits only apply to the next call
+        if let Some(limit) = self.conflict_limit {
+            let name = CString::new("conflicts").unwrap();
+            let limit = limit.min(i32::MAX as u32) as c_int;
+            unsafe { ccadical_limit(self.ptr, name.as_ptr(), limit) };
+        }
+        for lit in assumptions {
+            self.max_variable = self.max_variable.max(lit.abs());
+            unsafe { ccadical_assume(self.ptr, *lit) };
+        }
+        let result = match unsafe { ccadical_solve(self.ptr) } {
+            10 => Some(true),
+            20 => Some(false),
+            _ => None,
+        };
+        if self.tracing {
+            unsafe { aba2sat_cadical_flush_proof_trace(self.ptr) };
+        }
+        result
+    }
+
+    fn value(&self, lit: RawLiteral) -> Option<bool> {
+        if lit.abs() > self.max_variable {
+            return None;
+        }
+        match unsafe { ccadical_val(self.ptr, lit) } {
+            val if val == lit => Some(true),
+            val if val == -lit => Some(false),
+            _ => None,
+        }
+    }
+
+    fn max_variable(&self) -> i32 {
+        self.max_variable
+    }
+
+    fn set_terminate(&mut self, token: CancellationToken) {
+        let token = Box::new(token);
+        let state = &*token as *const CancellationToken as *mut c_void;
+        unsafe { ccadical_set_terminate(self.ptr, state, Some(Cadical::terminate_callback)) };
+        // The previous token is dropped only after the solver stopped using it
+        self.terminate = Some(token);
+    }
+
+    fn set_conflict_limit(&mut self, limit: Option<u32>) {
+        self.conflict_limit = limit;
+    }
+
+    /// The proof is written in the text format, like the proofs of the other backends
+    fn trace_proof(&mut self, path: &Path) -> io::Result<()> {
+        let error =
+            |why: String| io::Error::other(format!("could not trace a proof to {path:?}: {why}"));
+        self.set_option("binary", 0).map_err(error)?;
+        // CaDiCaL does not report a file it cannot open, it fails when writing to it
+        std::fs::File::create(path).map_err(|why| error(why.to_string()))?;
+        let c_path = path
+            .to_str()
+            .and_then(|path| CString::new(path).ok())
+            .ok_or_else(|| error("unsupported path".to_owned()))?;
+        if unsafe { aba2sat_cadical_trace_proof(self.ptr, c_path.as_ptr()) } == 0 {
+            return Err(error("CaDiCaL failed to open it".to_owned()));
+        }
+        self.tracing = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sat::proof::tests::check_proof;
+
+    use super::*;
+
+    #[test]
+    fn assumptions_and_termination() {
+        let mut sat = Cadical::default();
+        sat.add_clause(vec![1, 2]);
+        sat.add_clause(vec![-1, 2]);
+        assert_eq!(sat.solve(), Some(true));
+        assert_eq!(sat.value(2), Some(true));
+        assert_eq!(sat.solve_with(&[-2]), Some(false));
+        // Assumptions only hold for a single call
+        assert_eq!(sat.solve(), Some(true));
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        sat.set_terminate(cancel);
+        assert_eq!(sat.solve(), None);
+    }
+
+    #[test]
+    fn conflict_limit() {
+        // Seven pigeons in six holes, `p * 6 + h + 1` if pigeon `p` is in hole `h`
+        let var = |pigeon: i32, hole: i32| pigeon * 6 + hole + 1;
+        let mut sat = Cadical::default();
+        for pigeon in 0..7 {
+            sat.add_clause((0..6).map(|hole| var(pigeon, hole)).collect());
+        }
+        for hole in 0..6 {
+            for first in 0..7 {
+                for second in first + 1..7 {
+                    sat.add_clause(vec![-var(first, hole), -var(second, hole)]);
+                }
+            }
+        }
+        sat.set_conflict_limit(Some(1));
+        assert_eq!(sat.solve(), None);
+        sat.set_conflict_limit(None);
+        assert_eq!(sat.solve(), Some(false));
+    }
+
+    #[test]
+    fn options_keep_results() {
+        for idx in 0..8 {
+            let options = CadicalOptions::default().diversified(idx);
+            let mut sat = Cadical::new(&options);
+            sat.add_clause(vec![1, 2]);
+            sat.add_clause(vec![-1]);
+            assert_eq!(sat.solve(), Some(true), "{options:?}");
+            assert_eq!(sat.value(2), Some(true));
+        }
+    }
+
+    #[test]
+    fn proof_of_unsatisfiability() {
+        // Six pigeons in five holes
+        let var = |pigeon: i32, hole: i32| pigeon * 5 + hole + 1;
+        let mut clauses: Vec<RawClause> = (0..6)
+            .map(|pigeon| (0..5).map(|hole| var(pigeon, hole)).collect())
+            .collect();
+        for hole in 0..5 {
+            for first in 0..6 {
+                for second in first + 1..6 {
+                    clauses.push(vec![-var(first, hole), -var(second, hole)]);
+                }
+            }
+        }
+        let path =
+            std::env::temp_dir().join(format!("aba2sat-cadical-{}.drat", std::process::id()));
+        let mut sat = Cadical::default();
+        sat.trace_proof(&path).unwrap();
+        clauses
+            .iter()
+            .for_each(|clause| sat.add_clause(clause.clone()));
+        assert_eq!(sat.solve(), Some(false));
+        // The proof is complete while the solver is still alive
+        let proof = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(check_proof(clauses, &proof), "{proof}");
+        // Tracing has to start before the first clause
+        assert!(sat.trace_proof(&path).is_err());
+    }
+}
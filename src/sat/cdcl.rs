@@ -0,0 +1,599 @@
+//! [`Backend`] using a small conflict-driven clause learning solver written in Rust
+//!
+//! This solver does not come close to the performance of CaDiCaL, but it does not
+//! need a C++ toolchain. It implements the usual techniques:
+//! - two watched literals per clause for unit propagation
+//! - first UIP clause learning with non-chronological backtracking
+//! - VSIDS branching with phase saving
+//! - Luby restarts
+//!
+//! Learnt clauses are never removed. Assumptions are decided before any other
+//! variable, every learnt clause is a consequence of the added clauses alone. This
+//! makes every learnt clause a valid lemma of a DRAT proof, see
+//! [`Backend::trace_proof`].
+use std::{
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+};
+
+use super::{dimacs::write_clause, Backend};
+
+/// Number of conflicts in a single unit of the Luby restart sequence
+const RESTART_UNIT: u64 = 100;
+/// Factor applied to the activity increment after every conflict
+const ACTIVITY_DECAY: f64 = 0.95;
+/// Activities are rescaled once they exceed this value
+const ACTIVITY_LIMIT: f64 = 1e100;
+
+/// Literal `2 * variable + negated`
+type Lit = usize;
+
+fn lit_of(raw: RawLiteral) -> Lit {
+    2 * raw.unsigned_abs() as usize + raw.is_negative() as usize
+}
+
+fn var_of(lit: Lit) -> usize {
+    lit / 2
+}
+
+fn raw_of(lit: Lit) -> RawLiteral {
+    let var = var_of(lit) as RawLiteral;
+    if lit % 2 == 1 {
+        -var
+    } else {
+        var
+    }
+}
+
+#[derive(Default)]
+pub struct Cdcl {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching a literal, indexed by that literal
+    watches: Vec<Vec<usize>>,
+    /// Current value of every variable
+    assigns: Vec<Option<bool>>,
+    /// Decision level a variable was assigned at
+    level: Vec<usize>,
+    /// Clause that implied the value of a variable, `None` for decisions
+    reason: Vec<Option<usize>>,
+    /// Assigned literals in assignment order
+    trail: Vec<Lit>,
+    /// Start of every decision level in the trail
+    trail_limits: Vec<usize>,
+    /// Next position of the trail to propagate
+    propagated: usize,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    /// Unassigned variables ordered by activity, may contain outdated entries
+    order: BinaryHeap<(u64, usize)>,
+    /// Last value of every variable, used for the next decision
+    phase: Vec<bool>,
+    /// Scratch space for the conflict analysis
+    seen: Vec<bool>,
+    model: Vec<Option<bool>>,
+    /// An empty clause was derived, no call can succeed anymore
+    inconsistent: bool,
+    terminate: Option<CancellationToken>,
+    /// Maximum number of conflicts per solver call
+    conflict_limit: Option<u32>,
+    /// DRAT proof all learnt clauses are written to
+    proof: Option<BufWriter<File>>,
+}
+
+impl Cdcl {
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[var_of(lit)].map(|value| value != (lit % 2 == 1))
+    }
+
+    /// Add `lemma` to the proof, the empty lemma concludes it
+    fn trace(&mut self, lemma: &[Lit]) {
+        let Some(proof) = &mut self.proof else {
+            return;
+        };
+        let lemma: Vec<_> = lemma.iter().cloned().map(raw_of).collect();
+        if let Err(why) = write_clause(proof, &lemma) {
+            log::error!("Writing the proof failed, stopping it: {why}");
+            self.proof = None;
+        }
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    /// Make sure `var` is known to the solver
+    fn reserve(&mut self, var: usize) {
+        while self.assigns.len() <= var {
+            let new = self.assigns.len();
+            self.assigns.push(None);
+            self.level.push(0);
+            self.reason.push(None);
+            self.activity.push(0.0);
+            self.phase.push(false);
+            self.seen.push(false);
+            self.watches.extend([vec![], vec![]]);
+            if new > 0 {
+                self.order.push((0, new));
+            }
+        }
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = var_of(lit);
+        self.assigns[var] = Some(lit % 2 == 0);
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Undo all assignments above `level`
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_limits[level];
+        for lit in self.trail.drain(start..) {
+            let var = var_of(lit);
+            self.phase[var] = self.assigns[var].unwrap_or_default();
+            self.assigns[var] = None;
+            self.reason[var] = None;
+            self.order.push((self.activity[var].to_bits(), var));
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let idx = self.clauses.len();
+        self.watches[clause[0]].push(idx);
+        self.watches[clause[1]].push(idx);
+        self.clauses.push(clause);
+        idx
+    }
+
+    /// Propagate all assignments on the trail, returns a conflicting clause
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+            let mut watchers = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = 0;
+            let mut conflict = None;
+            let mut idx = 0;
+            while idx < watchers.len() {
+                let clause_idx = watchers[idx];
+                idx += 1;
+                let clause = &mut self.clauses[clause_idx];
+                // Make sure the false literal is the second watch
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                let value = |lit: Lit| self.assigns[var_of(lit)].map(|v| v != (lit % 2 == 1));
+                if value(first) == Some(true) {
+                    watchers[kept] = clause_idx;
+                    kept += 1;
+                    continue;
+                }
+                // Look for a new literal to watch
+                if let Some(pos) = (2..clause.len()).find(|pos| value(clause[*pos]) != Some(false))
+                {
+                    clause.swap(1, pos);
+                    let watch = clause[1];
+                    self.watches[watch].push(clause_idx);
+                    continue;
+                }
+                watchers[kept] = clause_idx;
+                kept += 1;
+                if value(first) == Some(false) {
+                    conflict = Some(clause_idx);
+                    // Keep the remaining watchers
+                    while idx < watchers.len() {
+                        watchers[kept] = watchers[idx];
+                        kept += 1;
+                        idx += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(clause_idx));
+                }
+            }
+            watchers.truncate(kept);
+            self.watches[false_lit] = watchers;
+            if conflict.is_some() {
+                self.propagated = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Learn the first UIP clause of the conflict, returns it with the backtrack level
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause_idx = conflict;
+        let mut implied = None;
+        let mut position = self.trail.len();
+        loop {
+            // The implied literal is the first of its reason
+            let skip = implied.is_some() as usize;
+            for pos in skip..self.clauses[clause_idx].len() {
+                let lit = self.clauses[clause_idx][pos];
+                let var = var_of(lit);
+                if self.seen[var] || self.level[var] == 0 {
+                    continue;
+                }
+                self.seen[var] = true;
+                self.bump(var);
+                if self.level[var] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            // Continue with the last seen literal of the current level
+            loop {
+                position -= 1;
+                if self.seen[var_of(self.trail[position])] {
+                    break;
+                }
+            }
+            let lit = self.trail[position];
+            self.seen[var_of(lit)] = false;
+            implied = Some(lit);
+            pending -= 1;
+            if pending == 0 {
+                break;
+            }
+            clause_idx = self.reason[var_of(lit)].expect("only decisions have no reason");
+        }
+        learnt[0] = implied.expect("the conflict contains a literal") ^ 1;
+        for lit in &learnt[1..] {
+            self.seen[var_of(*lit)] = false;
+        }
+        // Watch the literal of the highest level next to the asserting one
+        let mut level = 0;
+        if let Some((pos, _)) = learnt
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by_key(|(_, lit)| self.level[var_of(**lit)])
+        {
+            learnt.swap(1, pos);
+            level = self.level[var_of(learnt[1])];
+        }
+        (learnt, level)
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+        if self.activity[var] > ACTIVITY_LIMIT {
+            self.activity
+                .iter_mut()
+                .for_each(|activity| *activity /= ACTIVITY_LIMIT);
+            self.activity_increment /= ACTIVITY_LIMIT;
+            let activity = &self.activity;
+            self.order = (1..self.assigns.len())
+                .filter(|var| self.assigns[*var].is_none())
+                .map(|var| (activity[var].to_bits(), var))
+                .collect();
+        }
+        if self.assigns[var].is_none() {
+            self.order.push((self.activity[var].to_bits(), var));
+        }
+    }
+
+    /// Unassigned variable of the highest activity
+    fn next_decision(&mut self) -> Option<usize> {
+        while let Some((_, var)) = self.order.pop() {
+            if self.assigns[var].is_none() {
+                return Some(var);
+            }
+        }
+        None
+    }
+
+    fn terminated(&self) -> bool {
+        self.terminate
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+impl Backend for Cdcl {
+    fn add_clause(&mut self, clause: RawClause) {
+        if self.inconsistent {
+            return;
+        }
+        self.backtrack(0);
+        let mut clause: Vec<Lit> = clause.into_iter().map(lit_of).collect();
+        if let Some(max) = clause.iter().map(|lit| var_of(*lit)).max() {
+            self.reserve(max);
+        }
+        clause.sort_unstable();
+        clause.dedup();
+        // Drop tautologies and satisfied clauses, remove false literals
+        if clause.windows(2).any(|pair| pair[0] ^ 1 == pair[1])
+            || clause.iter().any(|lit| self.lit_value(*lit) == Some(true))
+        {
+            return;
+        }
+        clause.retain(|lit| self.lit_value(*lit).is_none());
+        match clause.len() {
+            0 => self.inconsistent = true,
+            1 => {
+                self.enqueue(clause[0], None);
+                self.inconsistent = self.propagate().is_some();
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+        if self.inconsistent {
+            self.trace(&[]);
+        }
+    }
+
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool> {
+        self.model.clear();
+        if self.inconsistent {
+            return Some(false);
+        }
+        let assumptions: Vec<Lit> = assumptions.iter().cloned().map(lit_of).collect();
+        if let Some(max) = assumptions.iter().map(|lit| var_of(*lit)).max() {
+            self.reserve(max);
+        }
+        if self.activity_increment == 0.0 {
+            self.activity_increment = 1.0;
+        }
+        let mut conflicts = 0;
+        let mut total_conflicts = 0;
+        let mut restart = 1;
+        let mut restart_limit = RESTART_UNIT * luby(restart);
+        let result = loop {
+            if self.terminated() {
+                break None;
+            }
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.inconsistent = true;
+                    self.trace(&[]);
+                    break Some(false);
+                }
+                conflicts += 1;
+                total_conflicts += 1;
+                if self
+                    .conflict_limit
+                    .is_some_and(|limit| total_conflicts > limit)
+                {
+                    break None;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.trace(&learnt);
+                self.backtrack(level);
+                match learnt.len() {
+                    1 => self.enqueue(learnt[0], None),
+                    _ => {
+                        let asserting = learnt[0];
+                        let idx = self.attach(learnt);
+                        self.enqueue(asserting, Some(idx));
+                    }
+                }
+                self.activity_increment /= ACTIVITY_DECAY;
+                continue;
+            }
+            if conflicts >= restart_limit {
+                conflicts = 0;
+                restart += 1;
+                restart_limit = RESTART_UNIT * luby(restart);
+                self.backtrack(0);
+                continue;
+            }
+            // Assumptions are decided first, one per level
+            if let Some(lit) = assumptions.get(self.decision_level()) {
+                match self.lit_value(*lit) {
+                    Some(false) => break Some(false),
+                    Some(true) => self.trail_limits.push(self.trail.len()),
+                    None => {
+                        self.trail_limits.push(self.trail.len());
+                        self.enqueue(*lit, None);
+                    }
+                }
+                continue;
+            }
+            let Some(var) = self.next_decision() else {
+                self.model = self.assigns.clone();
+                break Some(true);
+            };
+            self.trail_limits.push(self.trail.len());
+            self.enqueue(2 * var + !self.phase[var] as usize, None);
+        };
+        self.backtrack(0);
+        if let Some(Err(why)) = self.proof.as_mut().map(Write::flush) {
+            log::error!("Writing the proof failed, stopping it: {why}");
+            self.proof = None;
+        }
+        result
+    }
+
+    fn value(&self, lit: RawLiteral) -> Option<bool> {
+        let value = (*self.model.get(lit.unsigned_abs() as usize)?)?;
+        Some(value == lit.is_positive())
+    }
+
+    fn max_variable(&self) -> i32 {
+        self.assigns.len().saturating_sub(1) as i32
+    }
+
+    fn set_terminate(&mut self, token: CancellationToken) {
+        self.terminate = Some(token);
+    }
+
+    fn set_conflict_limit(&mut self, limit: Option<u32>) {
+        self.conflict_limit = limit;
+    }
+
+    /// Only lemmas learnt from now on are written, start before the first solver call
+    fn trace_proof(&mut self, path: &Path) -> io::Result<()> {
+        self.proof = Some(BufWriter::new(File::create(path)?));
+        Ok(())
+    }
+}
+
+/// Element `idx` of the Luby sequence `1 1 2 1 1 2 4 1 ...`, starting at one
+fn luby(mut idx: u64) -> u64 {
+    loop {
+        // Find the full subsequence of length 2^k - 1 containing `idx`
+        let mut k = 1;
+        while (1 << k) - 1 < idx {
+            k += 1;
+        }
+        if idx == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        idx -= (1 << (k - 1)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sat::proof::tests::check_proof;
+
+    use super::*;
+
+    /// Every assignment of `vars` variables satisfying all clauses
+    fn brute_force(vars: i32, clauses: &[RawClause]) -> bool {
+        (0..1u32 << vars).any(|bits| {
+            clauses.iter().all(|clause| {
+                clause.iter().any(|lit| {
+                    let value = bits & (1 << (lit.abs() - 1)) != 0;
+                    value == lit.is_positive()
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn luby_sequence() {
+        let seq: Vec<_> = (1..=15).map(luby).collect();
+        assert_eq!(seq, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    /// One pigeon more than `holes`, `p * holes + h + 1` if pigeon `p` is in hole `h`
+    fn pigeonhole(holes: i32) -> Vec<RawClause> {
+        let var = |pigeon: i32, hole: i32| pigeon * holes + hole + 1;
+        let mut clauses = vec![];
+        for pigeon in 0..=holes {
+            clauses.push((0..holes).map(|hole| var(pigeon, hole)).collect());
+        }
+        for hole in 0..holes {
+            for first in 0..=holes {
+                for second in first + 1..=holes {
+                    clauses.push(vec![-var(first, hole), -var(second, hole)]);
+                }
+            }
+        }
+        clauses
+    }
+
+    #[test]
+    fn pigeonhole_is_unsat() {
+        let mut sat = Cdcl::default();
+        for clause in pigeonhole(3) {
+            sat.add_clause(clause);
+        }
+        assert_eq!(sat.solve(), Some(false));
+    }
+
+    #[test]
+    fn proof_of_unsatisfiability() {
+        let path = std::env::temp_dir().join(format!("aba2sat-cdcl-{}.drat", std::process::id()));
+        let mut sat = Cdcl::default();
+        sat.trace_proof(&path).unwrap();
+        for clause in pigeonhole(4) {
+            sat.add_clause(clause);
+        }
+        assert_eq!(sat.solve(), Some(false));
+        let proof = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(proof.lines().count() > 1);
+        assert!(check_proof(pigeonhole(4), &proof), "{proof}");
+        // The checker rejects lemmas that do not follow
+        assert!(!check_proof(pigeonhole(4), "1 0\n0\n"));
+    }
+
+    #[test]
+    fn random_formulas_match_brute_force() {
+        // Simple linear congruential generator to stay deterministic
+        let mut state: u64 = 0x2545F4914F6CDD1D;
+        let mut next = |max: u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 33) % max
+        };
+        for _ in 0..300 {
+            let vars = 1 + next(8) as i32;
+            let clauses: Vec<RawClause> = (0..next(30))
+                .map(|_| {
+                    (0..1 + next(3))
+                        .map(|_| {
+                            let var = 1 + next(vars as u64) as i32;
+                            if next(2) == 0 {
+                                var
+                            } else {
+                                -var
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut sat = Cdcl::default();
+            clauses
+                .iter()
+                .for_each(|clause| sat.add_clause(clause.clone()));
+            let expected = brute_force(vars, &clauses);
+            assert_eq!(sat.solve(), Some(expected), "{clauses:?}");
+            if expected {
+                // The model must satisfy every clause
+                assert!(clauses
+                    .iter()
+                    .all(|clause| clause.iter().any(|lit| sat.value(*lit) == Some(true))));
+            }
+            // Assumptions only hold for a single call
+            let assumption = 1 + next(vars as u64) as i32;
+            let mut assumed = clauses.clone();
+            assumed.push(vec![assumption]);
+            let expected_assumed = brute_force(vars, &assumed);
+            assert_eq!(sat.solve_with(&[assumption]), Some(expected_assumed));
+            assert_eq!(sat.solve(), Some(expected), "{clauses:?}");
+        }
+    }
+
+    #[test]
+    fn termination() {
+        let mut sat = Cdcl::default();
+        sat.add_clause(vec![1, 2]);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        sat.set_terminate(cancel);
+        assert_eq!(sat.solve(), None);
+    }
+
+    #[test]
+    fn conflict_limit() {
+        let mut sat = Cdcl::default();
+        for clause in pigeonhole(6) {
+            sat.add_clause(clause);
+        }
+        sat.set_conflict_limit(Some(10));
+        assert_eq!(sat.solve(), None);
+        sat.set_conflict_limit(None);
+        assert_eq!(sat.solve(), Some(false));
+    }
+}
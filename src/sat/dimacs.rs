@@ -0,0 +1,32 @@
+//! Writing formulas in the DIMACS CNF format
+use std::io::{self, Write};
+
+use crate::clauses::{RawClause, RawLiteral};
+
+/// Write `clauses` and the `units` as a DIMACS file with `max_variable` variables
+pub fn write_dimacs<W: Write>(
+    out: &mut W,
+    max_variable: i32,
+    clauses: &[RawClause],
+    units: &[RawLiteral],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "p cnf {} {}",
+        max_variable,
+        clauses.len() + units.len()
+    )?;
+    let units = units.iter().map(std::slice::from_ref);
+    for clause in clauses.iter().map(Vec::as_slice).chain(units) {
+        write_clause(out, clause)?;
+    }
+    Ok(())
+}
+
+/// Write a single clause terminated by `0`, the empty clause is just `0`
+pub fn write_clause<W: Write>(out: &mut W, clause: &[RawLiteral]) -> io::Result<()> {
+    for lit in clause {
+        write!(out, "{lit} ")?;
+    }
+    writeln!(out, "0")
+}
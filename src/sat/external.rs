@@ -0,0 +1,262 @@
+//! [`Backend`] running an external solver binary on DIMACS files
+//!
+//! Every solver call writes all clauses added so far, together with the assumptions
+//! as unit clauses, into a temporary DIMACS file. The file is passed as the last
+//! argument to the configured command. The output is expected in the format of the
+//! SAT competition, a status line `s SATISFIABLE` or `s UNSATISFIABLE`, followed by
+//! the model in `v ...` lines.
+//!
+//! If a proof is traced, its path is passed after the DIMACS file, like CaDiCaL
+//! and Kissat expect it. Every call overwrites the proof of the previous one.
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+};
+
+use super::{dimacs::write_dimacs, Backend};
+
+/// Interval in which a running solver is checked for termination
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Counter to create unique file names for concurrent solver calls
+static FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Command line of an external solver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalSolver {
+    pub program: PathBuf,
+    /// Arguments passed before the path of the DIMACS file
+    pub args: Vec<String>,
+}
+
+pub struct External {
+    solver: ExternalSolver,
+    clauses: Vec<RawClause>,
+    max_variable: i32,
+    /// Model of the last satisfiable call, indexed by variable
+    model: Vec<Option<bool>>,
+    terminate: Option<CancellationToken>,
+    /// Path the solver writes its proof to
+    proof: Option<PathBuf>,
+    /// Why the last call failed, see [`Backend::take_error`]
+    error: Option<io::Error>,
+}
+
+impl External {
+    pub fn new(solver: ExternalSolver) -> Self {
+        External {
+            solver,
+            clauses: vec![],
+            max_variable: 0,
+            model: vec![],
+            terminate: None,
+            proof: None,
+            error: None,
+        }
+    }
+
+    fn write_dimacs(&self, path: &Path, assumptions: &[RawLiteral]) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_dimacs(&mut file, self.max_variable, &self.clauses, assumptions)?;
+        file.flush()
+    }
+
+    /// Run the solver on `path` and collect its output, `None` if it was terminated
+    fn run(&self, path: &Path) -> io::Result<Option<String>> {
+        let mut child = Command::new(&self.solver.program)
+            .args(&self.solver.args)
+            .arg(path)
+            .args(&self.proof)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // Read the output concurrently, the solver may block on a full pipe otherwise
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = std::thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+        loop {
+            if child.try_wait()?.is_some() {
+                break;
+            }
+            if self
+                .terminate
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                child.kill()?;
+                child.wait()?;
+                return Ok(None);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        let output = reader.join().expect("reading the solver output panicked")?;
+        Ok(Some(output))
+    }
+}
+
+impl Backend for External {
+    fn add_clause(&mut self, clause: RawClause) {
+        let max = clause.iter().map(|lit| lit.abs()).max().unwrap_or_default();
+        self.max_variable = self.max_variable.max(max);
+        self.clauses.push(clause);
+    }
+
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool> {
+        let max = assumptions.iter().map(|lit| lit.abs()).max();
+        self.max_variable = self.max_variable.max(max.unwrap_or_default());
+        self.model.clear();
+        self.error = None;
+        let path = std::env::temp_dir().join(format!(
+            "aba2sat-{}-{}.cnf",
+            std::process::id(),
+            FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let output = self
+            .write_dimacs(&path, assumptions)
+            .and_then(|()| self.run(&path));
+        if let Err(why) = std::fs::remove_file(&path) {
+            log::warn!("Failed to remove {path:?}: {why}");
+        }
+        let output = match output {
+            Ok(output) => output?,
+            Err(why) => {
+                let why = io::Error::new(
+                    why.kind(),
+                    format!("could not run {:?}: {why}", self.solver.program),
+                );
+                self.error = Some(why);
+                return None;
+            }
+        };
+        let Some((result, model)) = parse_output(&output) else {
+            self.error = Some(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} did not report a result", self.solver.program),
+            ));
+            return None;
+        };
+        self.model = model;
+        Some(result)
+    }
+
+    fn value(&self, lit: RawLiteral) -> Option<bool> {
+        let value = (*self.model.get(lit.unsigned_abs() as usize)?)?;
+        Some(value == lit.is_positive())
+    }
+
+    fn max_variable(&self) -> i32 {
+        self.max_variable
+    }
+
+    fn set_terminate(&mut self, token: CancellationToken) {
+        self.terminate = Some(token);
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn trace_proof(&mut self, path: &Path) -> io::Result<()> {
+        self.proof = Some(path.to_owned());
+        Ok(())
+    }
+}
+
+/// Parse the status and model printed by a solver, `None` for an unknown status
+fn parse_output(output: &str) -> Option<(bool, Vec<Option<bool>>)> {
+    let mut status = None;
+    let mut model = vec![];
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("s ") {
+            status = match rest.trim() {
+                "SATISFIABLE" => Some(true),
+                "UNSATISFIABLE" => Some(false),
+                _ => None,
+            };
+        } else if let Some(rest) = line.strip_prefix("v ") {
+            for lit in rest.split_whitespace() {
+                let Ok(lit) = lit.parse::<RawLiteral>() else {
+                    log::warn!("Ignoring invalid literal {lit:?} in the model");
+                    continue;
+                };
+                let variable = lit.unsigned_abs() as usize;
+                if variable == 0 {
+                    continue;
+                }
+                if model.len() <= variable {
+                    model.resize(variable + 1, None);
+                }
+                model[variable] = Some(lit.is_positive());
+            }
+        }
+    }
+    status.map(|status| (status, model))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_competition_output() {
+        let output = "c comment\ns SATISFIABLE\nv 1 -2\nv 3 0\n";
+        let (result, model) = parse_output(output).unwrap();
+        assert!(result);
+        assert_eq!(model, vec![None, Some(true), Some(false), Some(true)]);
+        let (result, _) = parse_output("s UNSATISFIABLE\n").unwrap();
+        assert!(!result);
+        assert_eq!(parse_output("s UNKNOWN\n"), None);
+    }
+
+    #[test]
+    fn run_stub_solver() {
+        // The DIMACS file is passed as `$1`, the stub just checks it exists
+        let mut sat = External::new(ExternalSolver {
+            program: PathBuf::from("sh"),
+            args: vec![
+                String::from("-c"),
+                String::from("test -f \"$1\" && echo 's SATISFIABLE' && echo 'v -1 2 0'"),
+                String::from("stub"),
+            ],
+        });
+        sat.add_clause(vec![-1, 2]);
+        assert_eq!(sat.solve(), Some(true));
+        assert_eq!(sat.value(1), Some(false));
+        assert_eq!(sat.value(-1), Some(true));
+        assert_eq!(sat.value(2), Some(true));
+        assert_eq!(sat.value(3), None);
+        assert_eq!(sat.max_variable(), 2);
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let mut sat = External::new(ExternalSolver {
+            program: PathBuf::from("/nonexistent/solver"),
+            args: vec![],
+        });
+        sat.add_clause(vec![1]);
+        assert_eq!(sat.solve(), None);
+        let why = sat.take_error().unwrap();
+        assert_eq!(why.kind(), io::ErrorKind::NotFound);
+        assert!(sat.take_error().is_none());
+        // A solver that does not print a status fails as well
+        let mut sat = External::new(ExternalSolver {
+            program: PathBuf::from("sh"),
+            args: vec![String::from("-c"), String::from("echo hello")],
+        });
+        assert_eq!(sat.solve(), None);
+        let why = sat.take_error().unwrap();
+        assert_eq!(why.kind(), io::ErrorKind::InvalidData);
+    }
+}
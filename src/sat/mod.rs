@@ -0,0 +1,107 @@
+//! SAT solvers that can be used to solve the encoded problems
+//!
+//! Every solver is wrapped in a [`Backend`]. The problem pipeline only talks to
+//! the trait, the concrete solver is picked with [`SolverBackend`].
+use std::{io, path::Path};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+};
+
+#[cfg(not(any(feature = "cadical", feature = "cdcl")))]
+compile_error!("At least one of the features `cadical` and `cdcl` is required");
+
+#[cfg(feature = "cadical")]
+pub mod cadical;
+#[cfg(feature = "cdcl")]
+pub mod cdcl;
+pub mod dimacs;
+pub mod external;
+pub mod portfolio;
+pub mod proof;
+
+/// Incremental SAT solver
+pub trait Backend: Send {
+    /// Add a clause to the formula
+    fn add_clause(&mut self, clause: RawClause);
+
+    /// Solve the formula, `None` if the solver was interrupted or failed, see
+    /// [`Backend::take_error`]
+    fn solve(&mut self) -> Option<bool> {
+        self.solve_with(&[])
+    }
+
+    /// Solve the formula with all `assumptions` fixed for this call only
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool>;
+
+    /// Value of `lit` in the model found by the last successful solver call
+    fn value(&self, lit: RawLiteral) -> Option<bool>;
+
+    /// The largest variable known to the solver
+    fn max_variable(&self) -> i32;
+
+    /// Interrupt running and future solver calls once `token` is cancelled
+    fn set_terminate(&mut self, token: CancellationToken);
+
+    /// Interrupt every solver call after `limit` conflicts, `None` removes the limit
+    fn set_conflict_limit(&mut self, limit: Option<u32>) {
+        if limit.is_some() {
+            log::warn!("The solver does not support conflict limits, ignoring it");
+        }
+    }
+
+    /// Why the last solver call returned `None`, if it failed instead of being interrupted
+    fn take_error(&mut self) -> Option<io::Error> {
+        None
+    }
+
+    /// Write a DRAT proof of unsatisfiability to `path`, see [`proof`]
+    fn trace_proof(&mut self, _path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the solver cannot write proofs",
+        ))
+    }
+}
+
+/// All available [`Backend`]s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverBackend {
+    /// The [CaDiCaL](https://github.com/arminbiere/cadical) solver
+    #[cfg(feature = "cadical")]
+    Cadical(cadical::CadicalOptions),
+    /// The solver of this crate, see [`cdcl`]
+    #[cfg(feature = "cdcl")]
+    Cdcl,
+    /// A solver binary that is called on DIMACS files
+    External(external::ExternalSolver),
+    /// Run all solvers in parallel and use the first answer
+    Portfolio(Vec<SolverBackend>),
+}
+
+/// CaDiCaL if it is available, the first solver linked into the binary otherwise
+impl Default for SolverBackend {
+    fn default() -> Self {
+        #[cfg(feature = "cadical")]
+        return SolverBackend::Cadical(cadical::CadicalOptions::default());
+        #[cfg(all(not(feature = "cadical"), feature = "cdcl"))]
+        return SolverBackend::Cdcl;
+    }
+}
+
+impl SolverBackend {
+    /// Create a new solver instance of this kind
+    pub fn create(&self) -> Box<dyn Backend> {
+        match self {
+            #[cfg(feature = "cadical")]
+            SolverBackend::Cadical(options) => Box::new(cadical::Cadical::new(options)),
+            #[cfg(feature = "cdcl")]
+            SolverBackend::Cdcl => Box::new(cdcl::Cdcl::default()),
+            SolverBackend::External(solver) => Box::new(external::External::new(solver.clone())),
+            SolverBackend::Portfolio(backends) => Box::new(portfolio::Portfolio::new(
+                backends.iter().map(SolverBackend::create).collect(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! [`Backend`] running multiple solvers in parallel
+//!
+//! Every clause is added to all solvers. A solver call starts all solvers on their
+//! own thread and returns the first answer, the other solvers are terminated.
+use std::{io, sync::mpsc};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+};
+
+use super::Backend;
+
+pub struct Portfolio {
+    solvers: Vec<Box<dyn Backend>>,
+    /// Solver that answered the last call
+    winner: Option<usize>,
+    terminate: Option<CancellationToken>,
+}
+
+impl Portfolio {
+    pub fn new(solvers: Vec<Box<dyn Backend>>) -> Self {
+        Portfolio {
+            solvers,
+            winner: None,
+            terminate: None,
+        }
+    }
+
+    fn terminated(&self) -> bool {
+        self.terminate
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+impl Backend for Portfolio {
+    fn add_clause(&mut self, clause: RawClause) {
+        for solver in &mut self.solvers {
+            solver.add_clause(clause.clone());
+        }
+    }
+
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool> {
+        self.winner = None;
+        if self.terminated() {
+            return None;
+        }
+        // Terminates the solvers that lost the race, and all of them once the
+        // portfolio is terminated
+        let stop = CancellationToken::linked(&self.terminate);
+        for solver in &mut self.solvers {
+            solver.set_terminate(stop.clone());
+        }
+        let (sender, receiver) = mpsc::channel();
+        let winner = std::thread::scope(|scope| {
+            for (idx, solver) in self.solvers.iter_mut().enumerate() {
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    // The receiver is gone once a winner is found
+                    let _ = sender.send((idx, solver.solve_with(assumptions)));
+                });
+            }
+            drop(sender);
+            // Solvers that failed or were terminated answer `None`, wait for the others
+            let winner = receiver
+                .iter()
+                .find_map(|(idx, result)| Some((idx, result?)));
+            // Terminate all other solvers, the scope waits for them
+            stop.cancel();
+            winner
+        });
+        let (idx, result) = winner?;
+        log::info!("Portfolio solver {idx} answered first");
+        self.winner = Some(idx);
+        Some(result)
+    }
+
+    fn value(&self, lit: RawLiteral) -> Option<bool> {
+        self.solvers[self.winner?].value(lit)
+    }
+
+    fn max_variable(&self) -> i32 {
+        self.solvers
+            .iter()
+            .map(|solver| solver.max_variable())
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn set_terminate(&mut self, token: CancellationToken) {
+        self.terminate = Some(token);
+    }
+
+    fn set_conflict_limit(&mut self, limit: Option<u32>) {
+        for solver in &mut self.solvers {
+            solver.set_conflict_limit(limit);
+        }
+    }
+
+    /// The error of the first solver that failed, if none answered
+    fn take_error(&mut self) -> Option<io::Error> {
+        let errors: Vec<_> = self
+            .solvers
+            .iter_mut()
+            .filter_map(|solver| solver.take_error())
+            .collect();
+        errors.into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sat::SolverBackend;
+
+    use super::*;
+
+    #[test]
+    fn first_answer_is_used() {
+        let backends = vec![SolverBackend::default(); 3];
+        let mut sat = SolverBackend::Portfolio(backends).create();
+        sat.add_clause(vec![1, 2]);
+        sat.add_clause(vec![-1, 2]);
+        assert_eq!(sat.solve(), Some(true));
+        assert_eq!(sat.value(2), Some(true));
+        assert_eq!(sat.solve_with(&[-2]), Some(false));
+        assert_eq!(sat.solve(), Some(true));
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        sat.set_terminate(cancel);
+        assert_eq!(sat.solve(), None);
+    }
+}
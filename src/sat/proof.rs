@@ -0,0 +1,192 @@
+//! Proofs of unsatisfiability that can be checked independently
+//!
+//! A [`Traced`] backend lets the wrapped solver write a DRAT proof and writes the
+//! formula the proof refers to next to it, see [`formula_path`]. The meaning of
+//! the variables is written by [`write_mapping`]. An unsatisfiable answer of the
+//! last solver call can then be checked with a proof checker like
+//! [drat-trim](https://github.com/marijnheule/drat-trim):
+//!
+//! ```text
+//! drat-trim proof.drat.cnf proof.drat
+//! ```
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cancel::CancellationToken,
+    clauses::{RawClause, RawLiteral},
+    mapper::Mapper,
+};
+
+use super::{dimacs::write_dimacs, Backend};
+
+/// A file next to the proof at `proof`, named like it with `extension` appended
+pub fn companion_path(proof: &Path, extension: &str) -> PathBuf {
+    let mut path = OsString::from(proof);
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Path of the DIMACS formula belonging to the proof at `proof`
+pub fn formula_path(proof: &Path) -> PathBuf {
+    companion_path(proof, "cnf")
+}
+
+/// Path of the variable mapping belonging to the proof at `proof`
+pub fn mapping_path(proof: &Path) -> PathBuf {
+    companion_path(proof, "map")
+}
+
+/// Write the literal of every variable of `map` next to the proof at `proof`
+///
+/// Every line holds a variable and its literal, e.g. `17 Attacker(4)`.
+pub fn write_mapping(proof: &Path, map: &Mapper) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(mapping_path(proof))?);
+    for (variable, literal) in map.variables() {
+        writeln!(file, "{variable} {literal}")?;
+    }
+    file.flush()
+}
+
+/// A [`Backend`] writing a proof, together with the formula of its last call
+pub struct Traced {
+    inner: Box<dyn Backend>,
+    formula: PathBuf,
+    clauses: Vec<RawClause>,
+    max_variable: i32,
+}
+
+impl Traced {
+    /// Let `inner` write its proof to `proof`, fails if it cannot write proofs
+    pub fn new(mut inner: Box<dyn Backend>, proof: &Path) -> io::Result<Self> {
+        inner.trace_proof(proof)?;
+        Ok(Traced {
+            inner,
+            formula: formula_path(proof),
+            clauses: vec![],
+            max_variable: 0,
+        })
+    }
+
+    /// The assumptions are part of the formula, as unit clauses
+    fn write_formula(&self, assumptions: &[RawLiteral]) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(&self.formula)?);
+        write_dimacs(&mut file, self.max_variable, &self.clauses, assumptions)?;
+        file.flush()
+    }
+}
+
+impl Backend for Traced {
+    fn add_clause(&mut self, clause: RawClause) {
+        let max = clause.iter().map(|lit| lit.abs()).max().unwrap_or_default();
+        self.max_variable = self.max_variable.max(max);
+        self.clauses.push(clause.clone());
+        self.inner.add_clause(clause);
+    }
+
+    fn solve_with(&mut self, assumptions: &[RawLiteral]) -> Option<bool> {
+        let max = assumptions.iter().map(|lit| lit.abs()).max();
+        self.max_variable = self.max_variable.max(max.unwrap_or_default());
+        if let Err(why) = self.write_formula(assumptions) {
+            log::error!("Writing the formula to {:?} failed: {why}", self.formula);
+        }
+        self.inner.solve_with(assumptions)
+    }
+
+    fn value(&self, lit: RawLiteral) -> Option<bool> {
+        self.inner.value(lit)
+    }
+
+    fn max_variable(&self) -> i32 {
+        self.inner.max_variable()
+    }
+
+    fn set_terminate(&mut self, token: CancellationToken) {
+        self.inner.set_terminate(token)
+    }
+
+    fn set_conflict_limit(&mut self, limit: Option<u32>) {
+        self.inner.set_conflict_limit(limit)
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.inner.take_error()
+    }
+
+    fn trace_proof(&mut self, path: &Path) -> io::Result<()> {
+        self.inner.trace_proof(path)?;
+        self.formula = formula_path(path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashSet;
+
+    use crate::clauses::{RawClause, RawLiteral};
+
+    /// The clauses of a DIMACS formula, header and comments are skipped
+    pub fn parse_formula(dimacs: &str) -> Vec<RawClause> {
+        dimacs
+            .lines()
+            .filter(|line| !line.starts_with(['c', 'p']))
+            .map(parse_clause)
+            .collect()
+    }
+
+    fn parse_clause(line: &str) -> RawClause {
+        line.split_whitespace()
+            .map(|lit| lit.parse().unwrap())
+            .take_while(|lit| *lit != 0)
+            .collect()
+    }
+
+    /// Whether every lemma of the DRAT `proof` follows from `clauses` and the lemmas
+    /// before it by unit propagation, and the empty clause is derived
+    ///
+    /// Deletions are ignored, more clauses never make a lemma fail this check.
+    pub fn check_proof(mut clauses: Vec<RawClause>, proof: &str) -> bool {
+        for line in proof.lines().filter(|line| !line.starts_with('d')) {
+            let lemma = parse_clause(line);
+            if !propagation_conflict(&clauses, &lemma) {
+                return false;
+            }
+            if lemma.is_empty() {
+                return true;
+            }
+            clauses.push(lemma);
+        }
+        false
+    }
+
+    /// Assume the negation of `lemma` and propagate until a conflict or a fixpoint
+    fn propagation_conflict(clauses: &[RawClause], lemma: &[RawLiteral]) -> bool {
+        let mut assigned: HashSet<_> = lemma.iter().map(|lit| -lit).collect();
+        loop {
+            let mut changed = false;
+            for clause in clauses {
+                if clause.iter().any(|lit| assigned.contains(lit)) {
+                    continue;
+                }
+                let open: Vec<_> = clause
+                    .iter()
+                    .filter(|lit| !assigned.contains(&-**lit))
+                    .collect();
+                match open[..] {
+                    [] => return true,
+                    [lit] => changed |= assigned.insert(*lit),
+                    _ => {}
+                }
+            }
+            if !changed {
+                return false;
+            }
+        }
+    }
+}
@@ -1,12 +1,41 @@
-use std::collections::HashSet;
-
-use crate::aba::{
-    debug::DebugAba,
-    problems::{
-        admissibility::{EnumerateAdmissibleExtensions, VerifyAdmissibleExtension},
-        complete::DecideCredulousComplete,
-        conflict_free::ConflictFreeness,
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    aba::{
+        check,
+        debug::DebugAba,
+        problems::{
+            acceptance::{
+                complete_acceptance, preferred_acceptance, stable_acceptance, Acceptance,
+            },
+            admissibility::{
+                credulous_shortcut, DecideCredulousAdmissibility, EnumerateAdmissibleExtensions,
+                VerifyAdmissibleExtension,
+            },
+            complete::{DecideCredulousComplete, EnumerateCompleteExtensions},
+            conflict_free::ConflictFreeness,
+            encode::encode,
+            limits::{Limits, Watchdog},
+            session::Session,
+            stable::EnumerateStableExtensions,
+            Config, Outcome,
+        },
+        statistics::{Encoding, Shortcut},
+        symmetry::Symmetries,
+        Aba, Context,
     },
+    cancel::CancellationToken,
+    clauses::ClauseSimplification,
+    literal::IntoLiteral,
+    mapper::Mapper,
+    sat::{
+        proof::tests::{check_proof, parse_formula},
+        SolverBackend,
+    },
+    Error,
 };
 
 fn simple_aba_example_1() -> DebugAba {
@@ -43,7 +72,7 @@ fn simple_conflict_free_verification() {
                     assumptions: translated,
                 },
                 aba.aba().clone(),
-                None
+                &Config::default()
             )
             .unwrap();
             assert!(
@@ -68,7 +97,7 @@ fn simple_admissible_verification() {
             eprintln!("Checking set {assumptions:?}");
             let translated= aba.forward_set(assumptions.clone()).unwrap();
             let result =
-                crate::aba::problems::solve(VerifyAdmissibleExtension { assumptions: translated }, aba.aba().clone(), None).unwrap();
+                crate::aba::problems::solve(VerifyAdmissibleExtension { assumptions: translated }, aba.aba().clone(), &Config::default()).unwrap();
             assert!(
                 result == expectation,
                 "Expected {expectation} from solver, but got {result} while checking {assumptions:?}"
@@ -83,7 +112,7 @@ fn simple_admissible_example() {
     let result = crate::aba::problems::multishot_solve(
         EnumerateAdmissibleExtensions::default(),
         aba.aba().clone(),
-        None,
+        &Config::default(),
     )
     .unwrap();
     for elem in aba.forward_sets(expected.clone()).unwrap() {
@@ -115,7 +144,7 @@ fn simple_admissible_example_with_defense() {
     let result = crate::aba::problems::multishot_solve(
         EnumerateAdmissibleExtensions::default(),
         aba.aba().clone(),
-        None,
+        &Config::default(),
     )
     .unwrap();
     for elem in aba.forward_sets(expected.clone()).unwrap() {
@@ -146,7 +175,7 @@ fn simple_admissible_atomic() {
     let result = crate::aba::problems::multishot_solve(
         EnumerateAdmissibleExtensions::default(),
         aba.aba().clone(),
-        None,
+        &Config::default(),
     )
     .unwrap();
     for elem in aba.forward_sets(expected.clone()).unwrap() {
@@ -177,7 +206,7 @@ fn a_chain_with_no_beginning() {
     let result = crate::aba::problems::multishot_solve(
         EnumerateAdmissibleExtensions::default(),
         aba.aba().clone(),
-        None,
+        &Config::default(),
     )
     .unwrap();
     for elem in aba.forward_sets(expected.clone()).unwrap() {
@@ -203,9 +232,12 @@ fn loops_and_conflicts() {
         .with_rule('c', ['b'])
         .with_rule('d', ['b']);
     let element = aba.forward_atom('d').unwrap();
-    let result =
-        crate::aba::problems::solve(DecideCredulousComplete { element }, aba.aba().clone(), None)
-            .unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
     assert!(!result, "d cannot be credulous complete");
 }
 
@@ -218,9 +250,12 @@ fn loops_and_conflicts_2() {
         .with_rule('d', ['c'])
         .with_rule('d', ['a']);
     let element = aba.forward_atom('b').unwrap();
-    let result =
-        crate::aba::problems::solve(DecideCredulousComplete { element }, aba.aba().clone(), None)
-            .unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
     assert!(result, "b is credulous complete");
 }
 
@@ -233,9 +268,12 @@ fn rules_with_trivial_cycles() {
         .with_rule('d', ['a'])
         .with_rule('d', ['d', 'c']);
     let element = aba.forward_atom('b').unwrap();
-    let result =
-        crate::aba::problems::solve(DecideCredulousComplete { element }, aba.aba().clone(), None)
-            .unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
     assert!(!result, "b is not credulous complete");
 }
 
@@ -251,9 +289,12 @@ fn infamous_eight() {
         .with_rule('f', ['c'])
         .with_rule('f', ['e']);
     let element = aba.forward_atom('a').unwrap();
-    let result =
-        crate::aba::problems::solve(DecideCredulousComplete { element }, aba.aba().clone(), None)
-            .unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
     assert!(!result, "a is not credulous complete");
 }
 
@@ -267,8 +308,769 @@ fn mystery() {
         .with_rule('e', ['d'])
         .with_rule('d', ['c']);
     let element = aba.forward_atom('a').unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
+    assert!(!result, "a is not credulous complete");
+}
+
+#[test]
+fn facts_attack_assumptions() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_rule('p', [])
+        .with_rule('q', ['p', 'a']);
+    let element = aba.forward_atom('a').unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
+    assert!(!result, "a is attacked by a fact");
+    let element = aba.forward_atom('b').unwrap();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
+    assert!(result, "b is only attacked using a");
+}
+
+#[test]
+fn facts_activate_their_rule_helper() {
+    // Rules of cyclic frameworks are never inlined, `f <- {}` gets a helper
+    let aba = DebugAba::default()
+        .with_assumption('a', 'b')
+        .with_assumption('d', 'e')
+        .with_rule('b', ['a'])
+        .with_rule('b', ['c'])
+        .with_rule('c', ['b'])
+        .with_rule('c', ['d'])
+        .with_rule('f', [])
+        .aba()
+        .clone()
+        .prepare(None);
+    let fact = aba
+        .rules
+        .iter()
+        .position(|(_, body)| body.is_empty())
+        .unwrap();
+    let map = Mapper::new(&aba);
+    let mut sat = SolverBackend::default().create();
+    map.as_raw_iter(aba.derive_clauses(Context::CANDIDATE))
+        .for_each(|clause| sat.add_clause(clause));
+    assert_eq!(sat.solve(), Some(true));
+    let active = map.as_raw(&Context::CANDIDATE.rule(fact).pos());
+    assert_eq!(
+        sat.value(active),
+        Some(true),
+        "rules without a body are active"
+    );
+}
+
+#[test]
+fn acyclic_frameworks_use_the_lean_encoding() {
+    let acyclic = simple_aba_example_1().aba().clone().prepare_until(
+        None,
+        None,
+        &CancellationToken::new(),
+        false,
+    );
+    assert_eq!(acyclic.statistics().encoding, Encoding::Acyclic);
+    assert_eq!(acyclic.statistics().inlined_rules, 3);
+    let cyclic = DebugAba::default()
+        .with_assumption('a', 'b')
+        .with_rule('b', ['a'])
+        .with_rule('b', ['c'])
+        .with_rule('c', ['b'])
+        .aba()
+        .clone()
+        .prepare_until(None, None, &CancellationToken::new(), false);
+    assert_eq!(cyclic.statistics().encoding, Encoding::Cyclic);
+    assert_eq!(cyclic.statistics().inlined_rules, 0);
+}
+
+#[test]
+fn unrelated_parts_do_not_change_acceptance() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['c'])
+        // Cyclic part that can neither attack nor defend `a`
+        .with_assumption('d', 's')
+        .with_rule('s', ['t'])
+        .with_rule('t', ['s'])
+        .with_rule('t', ['d']);
+    for (element, expectation) in [('a', true), ('b', false), ('d', false)] {
+        let element = aba.forward_atom(element).unwrap();
+        let result = crate::aba::problems::solve(
+            DecideCredulousComplete { element },
+            aba.aba().clone(),
+            &Config::default(),
+        )
+        .unwrap();
+        assert_eq!(result, expectation);
+    }
+}
+
+#[test]
+fn grounded_propagation_keeps_complete_extensions() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('q', ['c'])
+        .with_rule('p', ['b'])
+        .with_rule('t', ['d'])
+        .with_rule('s', ['e']);
+    let expected: Vec<HashSet<char>> =
+        vec![set!('a', 'c'), set!('a', 'c', 'd'), set!('a', 'c', 'e')];
+    let config = Config {
+        grounded: true,
+        ..Default::default()
+    };
+    let result = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    let mut result = aba.backward_sets(result).unwrap();
+    result.sort_by_key(|set| {
+        let mut set: Vec<_> = set.iter().cloned().collect();
+        set.sort();
+        set
+    });
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn shortcuts_answer_simple_queries() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('r', [])
+        .with_rule('q', ['a'])
+        .with_rule('q', ['d'])
+        .with_rule('s', ['e'])
+        .with_rule('t', ['d']);
+    let prepared = aba.aba().clone().prepare(None);
+    let shortcut = |element| credulous_shortcut(&prepared, aba.forward_atom(element).unwrap());
+    assert_eq!(shortcut('a'), Some((Shortcut::Unattackable, true)));
+    assert_eq!(shortcut('b'), Some((Shortcut::Defeated, false)));
+    assert_eq!(shortcut('c'), Some((Shortcut::ContraryIsFact, false)));
+    assert_eq!(shortcut('q'), Some((Shortcut::Grounded, true)));
+    // Needs the solver to pick a side
+    assert_eq!(shortcut('d'), None);
+}
+
+#[test]
+fn clause_simplification_keeps_complete_extensions() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_rule('q', ['a', 'c'])
+        .with_rule('r', ['a'])
+        .with_rule('r', ['s'])
+        .with_rule('s', ['r']);
+    let extensions = |clauses| {
+        let mut extensions = crate::aba::problems::multishot_solve(
+            EnumerateCompleteExtensions::default(),
+            aba.aba().clone(),
+            &Config {
+                clauses,
+                ..Config::default()
+            },
+        )
+        .unwrap();
+        extensions.sort_by_key(|extension| {
+            let mut sorted: Vec<_> = extension.iter().cloned().collect();
+            sorted.sort_unstable();
+            sorted
+        });
+        extensions
+    };
+    let expected = extensions(ClauseSimplification::Off);
+    assert_eq!(expected.len(), 3);
+    assert_eq!(extensions(ClauseSimplification::Basic), expected);
+    assert_eq!(extensions(ClauseSimplification::Full), expected);
+}
+
+#[test]
+fn splitting_finds_all_complete_extensions() {
+    let aba = DebugAba::default()
+        // `a` and `b` attack each other, `c` is attacked by `a`
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_rule('r', ['a'])
+        // Independent part, `d` and `e` attack each other
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('s', ['e'])
+        .with_rule('t', ['d']);
+    let config = Config::default();
+    let expected = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
     let result =
-        crate::aba::problems::solve(DecideCredulousComplete { element }, aba.aba().clone(), None)
+        crate::aba::problems::split::enumerate_complete(aba.aba().clone(), &config).unwrap();
+    assert_eq!(result.len(), expected.len());
+    for extension in &expected {
+        assert!(result.contains(extension), "{extension:?} is missing");
+    }
+    let count = crate::aba::problems::split::count_complete(aba.aba().clone(), &config).unwrap();
+    assert_eq!(count, 9);
+}
+
+#[test]
+fn symmetry_breaking_keeps_all_extensions() {
+    let aba = DebugAba::default()
+        // Two interchangeable pairs of mutually attacking assumptions
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_assumption('c', 'r')
+        .with_assumption('d', 's')
+        .with_rule('r', ['d'])
+        .with_rule('s', ['c']);
+    let symmetries = Symmetries::of(&aba.aba().clone().prepare(None), &[]);
+    assert!(!symmetries.is_empty());
+    let config = Config {
+        symmetries: true,
+        ..Default::default()
+    };
+    let expected = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
+    let result = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert_eq!(result.len(), 9);
+    assert_eq!(result.len(), expected.len());
+    for extension in &expected {
+        assert!(result.contains(extension), "{extension:?} is missing");
+    }
+    let admissible = crate::aba::problems::multishot_solve(
+        EnumerateAdmissibleExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert_eq!(admissible.len(), 9);
+}
+
+#[test]
+fn portfolio_finds_all_complete_extensions() {
+    let aba = simple_aba_example_1();
+    let expected = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &Config::default(),
+    )
+    .unwrap();
+    let config = Config {
+        backend: SolverBackend::Portfolio(vec![SolverBackend::default(); 4]),
+        ..Default::default()
+    };
+    let result = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert_eq!(result.len(), expected.len());
+    for extension in &expected {
+        assert!(result.contains(extension), "{extension:?} is missing");
+    }
+}
+
+#[test]
+fn parallel_enumeration_is_deterministic() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_rule('r', ['a'])
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('s', ['e'])
+        .with_rule('t', ['d']);
+    let config = Config::default();
+    let expected = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    let single = crate::aba::problems::parallel::enumerate_complete(aba.aba().clone(), &config, 1)
+        .unwrap()
+        .complete()
+        .unwrap();
+    assert_eq!(single.len(), expected.len());
+    for extension in &expected {
+        assert!(single.contains(extension), "{extension:?} is missing");
+    }
+    for threads in [2, 3, 8] {
+        let result =
+            crate::aba::problems::parallel::enumerate_complete(aba.aba().clone(), &config, threads)
+                .unwrap()
+                .complete()
+                .unwrap();
+        assert_eq!(result, single);
+    }
+}
+
+#[test]
+fn session_answers_like_single_queries() {
+    let aba = simple_aba_example_1()
+        .with_assumption('d', 'u')
+        .with_rule('u', ['a'])
+        .with_rule('s', ['d']);
+    for shortcuts in [true, false] {
+        let config = Config {
+            shortcuts,
+            ..Default::default()
+        };
+        let mut session = Session::new(aba.aba().clone(), &config).unwrap();
+        for element in ['a', 'b', 'c', 'd', 'p', 'q', 'r', 's', 't', 'u'] {
+            let element = aba.forward_atom(element).unwrap();
+            let expected = crate::aba::problems::solve(
+                DecideCredulousComplete { element },
+                aba.aba().clone(),
+                &config,
+            )
             .unwrap();
-    assert!(!result, "a is not credulous complete");
+            assert_eq!(
+                session.decide_credulous_complete(element).unwrap(),
+                expected
+            );
+        }
+        let admissible = crate::aba::problems::multishot_solve(
+            EnumerateAdmissibleExtensions::default(),
+            aba.aba().clone(),
+            &config,
+        )
+        .unwrap();
+        for set in [set!('a'), set!('b'), set!('a', 'c'), set!('b', 'c', 'd')] {
+            let set = aba.forward_set(set).unwrap();
+            let expected = admissible.contains(&set);
+            assert_eq!(session.verify_admissible(&set).unwrap(), expected);
+        }
+        assert!(session.decide_credulous_admissible(100).is_err());
+    }
+}
+
+#[test]
+fn acceptance_of_all_assumptions() {
+    // `d` is defended by both `a` and `b`, but not by the grounded extension
+    let floating = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_rule('r', ['a'])
+        .with_rule('r', ['b'])
+        .with_rule('s', ['c']);
+    // `f` attacks itself, there is no stable extension
+    let self_attacking = floating
+        .clone()
+        .with_assumption('f', 'u')
+        .with_rule('u', ['f']);
+    for shortcuts in [true, false] {
+        let config = Config {
+            shortcuts,
+            ..Default::default()
+        };
+        let accept = |aba: &DebugAba, semantics: fn(Aba, &Config) -> crate::Result<Acceptance>| {
+            let acceptance = semantics(aba.aba().clone(), &config).unwrap();
+            // Every semantics checked here has admissible witnesses
+            for witness in &acceptance.witnesses {
+                assert_eq!(check::admissible(aba.aba(), witness), Ok(()));
+            }
+            (
+                aba.backward_set(acceptance.credulous).unwrap(),
+                aba.backward_set(acceptance.skeptical).unwrap(),
+            )
+        };
+        let expected: (HashSet<char>, HashSet<char>) = (set!('a', 'b', 'd', 'e'), set!('e'));
+        assert_eq!(accept(&floating, complete_acceptance), expected);
+        assert_eq!(accept(&self_attacking, complete_acceptance), expected);
+        let expected: (HashSet<char>, HashSet<char>) = (set!('a', 'b', 'd', 'e'), set!('d', 'e'));
+        assert_eq!(accept(&floating, stable_acceptance), expected);
+        assert_eq!(accept(&floating, preferred_acceptance), expected);
+        assert_eq!(accept(&self_attacking, preferred_acceptance), expected);
+        let expected: (HashSet<char>, HashSet<char>) = (set!(), set!('a', 'b', 'c', 'd', 'e', 'f'));
+        assert_eq!(accept(&self_attacking, stable_acceptance), expected);
+    }
+}
+
+#[test]
+fn report_compares_all_semantics() {
+    let aba = Aba::default()
+        .with_assumption(1, 4)
+        .with_assumption(2, 5)
+        .with_assumption(3, 6)
+        .with_rule(4, [2])
+        .with_rule(5, [1])
+        .with_rule(6, [3]);
+    let report = crate::aba::problems::report::report(aba, &Config::default()).unwrap();
+    assert_eq!(report.assumptions, vec![1, 2, 3]);
+    assert_eq!(
+        report.to_json().unwrap(),
+        concat!(
+            r#"{"assumptions":[1,2,3],"semantics":{"#,
+            r#""cf":{"exists":true,"credulous":[1,2],"skeptical":[]},"#,
+            r#""ad":{"exists":true,"credulous":[1,2],"skeptical":[]},"#,
+            r#""co":{"exists":true,"credulous":[1,2],"skeptical":[]},"#,
+            r#""gr":{"exists":true,"credulous":[],"skeptical":[]},"#,
+            r#""st":{"exists":false,"credulous":[],"skeptical":[1,2,3]},"#,
+            r#""pr":{"exists":true,"credulous":[1,2],"skeptical":[]}}}"#
+        )
+    );
+    let table = report.to_table().unwrap();
+    assert_eq!(table.lines().nth(1), Some("exists yes yes yes yes  no yes"));
+    assert_eq!(table.lines().nth(4), Some("3       --  --  --  --  -s  --"));
+}
+
+#[test]
+fn limits_interrupt_the_solver() {
+    let aba = simple_aba_example_1();
+    // Unsatisfiable queries may be answered while adding the clauses
+    let element = aba.forward_atom('b').unwrap();
+    let config = Config {
+        shortcuts: false,
+        limits: Limits {
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &config,
+    );
+    assert!(matches!(result, Err(Error::Timeout)));
+    let result = crate::aba::problems::multishot_solve_partial(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert!(matches!(result, Outcome::Partial(found, Error::Timeout) if found.is_empty()));
+    // The cubes stopped after the first interruption do not hide the time limit
+    let result =
+        crate::aba::problems::parallel::enumerate_complete(aba.aba().clone(), &config, 4).unwrap();
+    assert!(matches!(result, Outcome::Partial(found, Error::Timeout) if found.is_empty()));
+    // Plenty of time and conflicts for this framework
+    let config = Config {
+        limits: Limits {
+            deadline: Some(Instant::now() + Duration::from_secs(600)),
+            solving: Some(Duration::from_secs(600)),
+            conflicts: Some(1000),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let result = crate::aba::problems::multishot_solve_partial(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert!(matches!(result, Outcome::Complete(found) if found.len() == 1));
+}
+
+#[test]
+fn watchdog_interrupts_at_the_deadline() {
+    let mut sat = SolverBackend::default().create();
+    sat.add_clause(vec![1, 2]);
+    let start = Instant::now();
+    let watchdog = Watchdog::new(
+        Some(start + Duration::from_millis(20)),
+        &CancellationToken::new(),
+    );
+    watchdog.watch(sat.as_mut(), &Limits::default());
+    while sat.solve().is_some() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "never interrupted"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    let why = watchdog.interruption(sat.as_mut(), &Limits::default());
+    assert!(matches!(why, Error::Timeout), "{why:?}");
+}
+
+#[test]
+fn solver_failures_are_not_limits() {
+    let aba = simple_aba_example_1();
+    let element = aba.forward_atom('b').unwrap();
+    let config = Config {
+        backend: SolverBackend::External(crate::sat::external::ExternalSolver {
+            program: std::path::PathBuf::from("/nonexistent/solver"),
+            args: vec![],
+        }),
+        limits: Limits {
+            conflicts: Some(1000),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &config,
+    );
+    assert!(matches!(result, Err(Error::Solver(_))), "{result:?}");
+}
+
+#[test]
+fn cancellation_is_per_problem() {
+    let aba = simple_aba_example_1();
+    let element = aba.forward_atom('b').unwrap();
+    let cancelled = Config {
+        shortcuts: false,
+        ..Default::default()
+    };
+    cancelled.cancel.cancel();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &cancelled,
+    );
+    assert!(matches!(result, Err(Error::Cancelled)));
+    let result = crate::aba::problems::multishot_solve_partial(
+        EnumerateCompleteExtensions::default(),
+        aba.aba().clone(),
+        &cancelled,
+    )
+    .unwrap();
+    assert!(matches!(result, Outcome::Partial(found, Error::Cancelled) if found.is_empty()));
+    // Another problem in the same process is not affected
+    let config = Config {
+        shortcuts: false,
+        ..Default::default()
+    };
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &config,
+    );
+    assert!(result.unwrap());
+    // Stopping the loop search still solves the problem
+    let config = Config {
+        shortcuts: false,
+        ..Default::default()
+    };
+    config.stop_loops.cancel();
+    let result = crate::aba::problems::solve(
+        DecideCredulousComplete { element },
+        aba.aba().clone(),
+        &config,
+    );
+    assert!(result.unwrap());
+}
+
+#[test]
+fn proof_of_a_rejected_query() {
+    let aba = DebugAba::default()
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_assumption('g', 'u')
+        // `a` is attacked by `b`, which is attacked by `c` and `d`
+        .with_rule('p', ['b'])
+        .with_rule('q', ['c'])
+        .with_rule('q', ['d'])
+        // `c` needs `d` to defend against `e` and the other way around with `g`
+        .with_rule('r', ['e'])
+        .with_rule('t', ['d'])
+        .with_rule('s', ['g'])
+        .with_rule('u', ['c'])
+        // But `c` attacks `d`, no admissible extension contains `a`
+        .with_rule('s', ['c']);
+    let element = aba.forward_atom('a').unwrap();
+    let proof = std::env::temp_dir().join(format!("aba2sat-test-{}.drat", std::process::id()));
+    // The default backend writes the proof itself
+    let config = Config {
+        shortcuts: false,
+        proof: Some(proof.clone()),
+        ..Default::default()
+    };
+    let result = crate::aba::problems::solve(
+        DecideCredulousAdmissibility { element },
+        aba.aba().clone(),
+        &config,
+    )
+    .unwrap();
+    assert!(!result);
+    let formula = crate::sat::proof::formula_path(&proof);
+    let mapping = crate::sat::proof::mapping_path(&proof);
+    let read = |path| {
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        content
+    };
+    let proof = read(proof);
+    let formula = read(formula);
+    assert!(formula.starts_with("p cnf "));
+    let formula = parse_formula(&formula);
+    // Unit propagation alone does not refute the formula, the proof is needed
+    assert!(!check_proof(formula.clone(), "0\n"));
+    assert!(check_proof(formula, &proof), "{proof}");
+    let mapping = read(mapping);
+    let candidate = format!("Candidate({})", aba.forward_atom('a').unwrap());
+    assert!(mapping.lines().any(|line| line.ends_with(&candidate)));
+}
+
+#[test]
+fn enumerated_extensions_pass_the_check() {
+    let aba = DebugAba::default()
+        // `a` and `b` attack each other, `c` is attacked by `a`
+        .with_assumption('a', 'p')
+        .with_assumption('b', 'q')
+        .with_assumption('c', 'r')
+        .with_rule('p', ['b'])
+        .with_rule('q', ['a'])
+        .with_rule('r', ['a'])
+        // `d` derives the contrary of `e` through a loop
+        .with_assumption('d', 's')
+        .with_assumption('e', 't')
+        .with_rule('u', ['d'])
+        .with_rule('u', ['v'])
+        .with_rule('v', ['u'])
+        .with_rule('t', ['v'])
+        .with_rule('s', ['e', 'c']);
+    let aba = aba.aba();
+    let assumptions: Vec<_> = aba.assumptions().cloned().collect();
+    let subsets: Vec<HashSet<_>> = (0..1u32 << assumptions.len())
+        .map(|bits| {
+            assumptions
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| bits & (1 << idx) != 0)
+                .map(|(_, assumption)| *assumption)
+                .collect()
+        })
+        .collect();
+    let passing = |check: fn(&Aba, &HashSet<_>) -> Result<(), _>| {
+        subsets
+            .iter()
+            .filter(|set| check(aba, set).is_ok())
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let config = Config::default();
+    let admissible = crate::aba::problems::multishot_solve(
+        EnumerateAdmissibleExtensions::default(),
+        aba.clone(),
+        &config,
+    )
+    .unwrap();
+    let complete = crate::aba::problems::multishot_solve(
+        EnumerateCompleteExtensions::default(),
+        aba.clone(),
+        &config,
+    )
+    .unwrap();
+    let stable = crate::aba::problems::multishot_solve(
+        EnumerateStableExtensions::default(),
+        aba.clone(),
+        &config,
+    )
+    .unwrap();
+    for (found, expected) in [
+        (admissible, passing(check::admissible)),
+        (complete, passing(check::complete)),
+        (stable, passing(check::stable)),
+    ] {
+        assert_eq!(found.len(), expected.len(), "{found:?} != {expected:?}");
+        for extension in &expected {
+            assert!(found.contains(extension), "{extension:?} is missing");
+        }
+    }
+    assert!(passing(check::conflict_free).len() > passing(check::admissible).len());
+}
+
+#[test]
+fn encoding_solves_like_the_problem() {
+    // `d` is never derivable, its only derivation needs the attacker of `a`
+    let cyclic = DebugAba::default()
+        .with_assumption('a', 'b')
+        .with_rule('b', ['a'])
+        .with_rule('b', ['c'])
+        .with_rule('c', ['b'])
+        .with_rule('d', ['b']);
+    let config = Config {
+        shortcuts: false,
+        ..Default::default()
+    };
+    let queries = [
+        (simple_aba_example_1(), 'a'),
+        (simple_aba_example_1(), 'p'),
+        (cyclic.clone(), 'a'),
+        (cyclic, 'd'),
+    ];
+    for (aba, element) in queries {
+        let element = aba.forward_atom(element).unwrap();
+        let problem = DecideCredulousComplete { element };
+        let encoding = encode(&problem, aba.aba().clone(), &config).unwrap();
+        let dimacs = encoding.to_dimacs().unwrap();
+        // Every variable is named before the problem line
+        let (comments, clauses) = dimacs.split_once("p cnf ").unwrap();
+        assert_eq!(
+            comments.lines().count(),
+            encoding.map.max_variable() as usize
+        );
+        assert!(comments.lines().any(|line| line.ends_with("Attacker(0)")));
+        let mut sat = config.backend.create();
+        for line in clauses.lines().skip(1) {
+            let clause: Vec<i32> = line
+                .split_whitespace()
+                .map(|lit| lit.parse().unwrap())
+                .take_while(|lit| *lit != 0)
+                .collect();
+            sat.add_clause(clause);
+        }
+        let expected = crate::aba::problems::solve(problem, aba.aba().clone(), &config).unwrap();
+        assert_eq!(sat.solve(), Some(expected), "{element}");
+    }
 }